@@ -64,6 +64,30 @@ async fn main() -> Result<()> {
     );
     req_list_task.await?.print().await?;
 
+    let req_create_task_rest = client.do_post(
+        "/api/tasks",
+        json!({
+            "title": "task CCC"
+        }),
+    );
+    req_create_task_rest.await?.print().await?;
+
+    client.do_get("/api/tasks").await?.print().await?;
+
+    client.do_delete("/api/tasks/1001").await?.print().await?;
+
+    let req_create_ticket = client.do_post(
+        "/api/tickets",
+        json!({
+            "title": "Ticket AAA"
+        })
+    );
+    req_create_ticket.await?.print().await?;
+
+    client.do_delete("/api/tickets/1000").await?.print().await?;
+
+    client.do_get("/api/tickets").await?.print().await?;
+
     let req_logout = client.do_post(
         "/api/logout",
         json!({
@@ -72,18 +96,6 @@ async fn main() -> Result<()> {
     );
     req_logout.await?.print().await?;
 
-    // let req_create_ticket = client.do_post(
-    //     "/api/tickets",
-    //     json!({
-    //         "title": "Ticket AAA"
-    //     })
-    // );
-    // req_create_ticket.await?.print().await?;
-    //
-    // client.do_delete("/api/tickets/1").await?.print().await?;
-    //
-    // client.do_get("/api/tickets").await?.print().await?;
-    //
     // client.do_get("/not-found").await?.print().await?;
     //
     // client.do_get("/index.html").await?.print().await?;
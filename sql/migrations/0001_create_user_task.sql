@@ -1,5 +1,3 @@
----- DATABASE SCHEMA
-
 -- User
 CREATE TABLE "user" (
     id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
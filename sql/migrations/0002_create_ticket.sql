@@ -0,0 +1,6 @@
+-- Ticket
+CREATE TABLE ticket (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    cid BIGINT NOT NULL,
+    title varchar(256) NOT NULL
+);
@@ -0,0 +1,30 @@
+-- Webhook
+CREATE TABLE webhook (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    cid BIGINT NOT NULL,
+    url varchar(2048) NOT NULL,
+    secret varchar(256) NOT NULL,
+    event_types varchar(64)[] NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT true,
+    -- Consecutive failed attempts, reset on success
+    failure_count INT NOT NULL DEFAULT 0
+);
+
+CREATE TABLE webhook_delivery (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    webhook_id BIGINT NOT NULL REFERENCES webhook (id) ON DELETE CASCADE,
+    event_id uuid NOT NULL,
+    event_type varchar(64) NOT NULL,
+    payload jsonb NOT NULL,
+    -- pending | delivered | failed
+    status varchar(16) NOT NULL DEFAULT 'pending',
+    attempts INT NOT NULL DEFAULT 0,
+    next_attempt_at timestamptz NOT NULL DEFAULT now(),
+    last_status_code INT,
+    last_error text,
+    ctime timestamptz NOT NULL DEFAULT now(),
+
+    UNIQUE (webhook_id, event_id)
+);
+
+CREATE INDEX webhook_delivery_due_idx ON webhook_delivery (next_attempt_at) WHERE status = 'pending';
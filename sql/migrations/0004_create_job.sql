@@ -0,0 +1,17 @@
+-- Job
+CREATE TABLE job (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    kind varchar(64) NOT NULL,
+    payload jsonb NOT NULL,
+    -- queued | running | done | dead
+    status varchar(16) NOT NULL DEFAULT 'queued',
+    attempts INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL DEFAULT 5,
+    run_at timestamptz NOT NULL DEFAULT now(),
+    -- Lease of a running job, it is picked again once expired
+    locked_until timestamptz,
+    last_error text,
+    ctime timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX job_due_idx ON job (run_at) WHERE status IN ('queued', 'running');
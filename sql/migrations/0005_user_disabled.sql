@@ -0,0 +1,2 @@
+-- A disabled user can neither log in nor use an existing token.
+ALTER TABLE "user" ADD COLUMN disabled BOOLEAN NOT NULL DEFAULT false;
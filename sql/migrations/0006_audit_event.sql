@@ -0,0 +1,30 @@
+-- Audit trail of the security and data-changing events
+CREATE TABLE audit_event (
+    id BIGINT GENERATED BY DEFAULT AS IDENTITY (START WITH 1000) PRIMARY KEY,
+    ctime timestamptz NOT NULL DEFAULT now(),
+    -- The user acting, 0 for the system (root ctx), NULL when unknown (e.g. a login of an unknown username)
+    actor_id BIGINT,
+    -- e.g. login.success, create, update
+    action varchar(64) NOT NULL,
+    entity varchar(64),
+    entity_id BIGINT,
+    -- { "before": {...}, "after": {...} }, with the secrets redacted
+    diff jsonb,
+    detail jsonb,
+    req_id varchar(128)
+);
+
+CREATE INDEX audit_event_actor_idx ON audit_event (actor_id, ctime);
+CREATE INDEX audit_event_entity_idx ON audit_event (entity, entity_id, ctime);
+CREATE INDEX audit_event_ctime_idx ON audit_event (ctime);
+
+-- Append-only, even for the application role
+CREATE FUNCTION audit_event_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_event is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_event_append_only
+    BEFORE UPDATE OR DELETE ON audit_event
+    FOR EACH ROW EXECUTE FUNCTION audit_event_append_only();
@@ -0,0 +1,12 @@
+-- Token buckets of the `postgres` rate limit store, shared by the instances
+-- Unlogged, losing them on a crash only resets the limits
+CREATE UNLOGGED TABLE rate_limit_bucket (
+    -- e.g. login:127.0.0.1, rpc:1000
+    key varchar(256) PRIMARY KEY,
+    tokens float8 NOT NULL,
+    mtime timestamptz NOT NULL DEFAULT now(),
+    -- Once full, the bucket can be deleted
+    full_at timestamptz NOT NULL DEFAULT now()
+);
+
+CREATE INDEX rate_limit_bucket_full_at_idx ON rate_limit_bucket (full_at);
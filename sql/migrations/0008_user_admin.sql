@@ -0,0 +1,2 @@
+-- Admins can read the audit trail and manage the jobs
+ALTER TABLE "user" ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT false;
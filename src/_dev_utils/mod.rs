@@ -2,7 +2,7 @@ mod dev_db;
 
 use std::error::Error;
 use tokio::sync::OnceCell;
-use tracing::log::info;
+use tracing::log::{error, info};
 use crate::ctx::Ctx;
 use crate::model;
 use crate::model::DbContext;
@@ -18,7 +18,7 @@ pub async fn init_dev() {
                 info!("{:<12} - Database initialized", "FOR-DEV-ONLY");
             }
             Err(e) => {
-                info!("{:<12} - Database error:{e:?}", "FOR-DEV-ONLY");
+                error!("{:<12} - Database error:{e:?}", "FOR-DEV-ONLY");
             }
         }
     })
@@ -47,9 +47,9 @@ pub async fn seed_task(
 
     for title in titles {
         let id =
-            TaskRepository::create(&ctx, &model_manager, TaskForCreate { title: title.to_string() })
+            TaskRepository::create(ctx, model_manager, TaskForCreate { title: title.to_string() })
                 .await?;
-        let task = TaskRepository::get(&ctx, &model_manager, id).await?;
+        let task = TaskRepository::get(ctx, model_manager, id).await?;
 
         tasks.push(task);
     }
@@ -0,0 +1,32 @@
+use std::fmt::Formatter;
+use derive_more::From;
+use crate::model::{self, migration};
+use crate::token;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+    #[from]
+    Model(model::Error),
+    #[from]
+    Migration(migration::Error),
+    #[from]
+    Token(token::Error),
+
+    UserNotFound { username: String },
+    /// `field: code`, without the rejected values (they may be passwords).
+    ValidationFail(Vec<String>),
+    PasswordsNotMatching,
+
+    #[from]
+    Io(std::io::Error),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
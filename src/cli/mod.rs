@@ -0,0 +1,246 @@
+mod error;
+
+pub use self::error::{Error, Result};
+
+use std::io::{self, BufRead, IsTerminal};
+use std::net::SocketAddr;
+use clap::{Args, Parser, Subcommand};
+use rand::RngCore;
+use validator::Validate;
+use crate::ctx::Ctx;
+use crate::model::migration::{self, MigrationState};
+use crate::model::user::{UserForAuth, UserForCreate, UserRepository};
+use crate::model::DbContext;
+use crate::token::{self, validate_web_token, Token};
+use crate::utils::base64_utils::b64u_encode;
+use crate::utils::time_utils::{format_time, now_utc, parse_utc};
+
+// region: -- Commands
+
+#[derive(Parser)]
+#[command(name = "webapi", version, about)]
+pub struct Cli {
+    /// Defaults to `serve`.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the web server.
+    Serve(ServeArgs),
+    /// Apply or list the database migrations.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Manage the users.
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Generate the keys used by the `SERVICE_*_KEY` settings.
+    #[command(subcommand)]
+    Keys(KeysCommand),
+    /// Inspect the auth tokens.
+    #[command(subcommand)]
+    Token(TokenCommand),
+}
+
+#[derive(Args, Default)]
+pub struct ServeArgs {
+    /// Overrides `SERVICE_BIND_ADDR`.
+    #[arg(long)]
+    pub addr: Option<SocketAddr>,
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// Apply the pending migrations, e.g. as a release step before rolling out the instances.
+    Up,
+    /// List the migrations and whether they are applied.
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum UserCommand {
+    /// Create a user, the password is read from stdin.
+    Create { username: String },
+    /// Change the password of a user, read from stdin.
+    SetPassword { username: String },
+    /// Refuse the logins and the existing tokens of a user.
+    Disable { username: String },
+    /// Allow a disabled user again.
+    Enable { username: String },
+    /// Allow a user to read the audit trail.
+    GrantAdmin { username: String },
+    RevokeAdmin { username: String },
+}
+
+#[derive(Subcommand)]
+pub enum KeysCommand {
+    /// Print a random key, base64url encoded.
+    Generate {
+        #[arg(long, default_value_t = 64)]
+        bytes: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TokenCommand {
+    /// Decode a token, then check its expiration and signature.
+    Inspect { token: String },
+}
+
+// endregion: -- Commands
+
+// region: -- Exec
+
+pub async fn exec_migrate(command: MigrateCommand) -> Result<()> {
+    let db_context = DbContext::new().await?;
+
+    match command {
+        MigrateCommand::Up => {
+            let applied = migration::migrate_up(&db_context).await?;
+            for migration in &applied {
+                println!("applied  {}", migration.name);
+            }
+            println!("{} migration(s) applied", applied.len());
+        }
+        MigrateCommand::Status => {
+            for status in migration::status(&db_context).await? {
+                let state = match status.state {
+                    MigrationState::Pending => "pending",
+                    MigrationState::Applied => "applied",
+                    MigrationState::ChecksumMismatch => "CHECKSUM MISMATCH",
+                };
+                let applied_at = status.applied_at.map(format_time).unwrap_or_default();
+                println!("{:<32} {:<18} {applied_at}", status.name, state);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn exec_user(command: UserCommand) -> Result<()> {
+    let db_context = DbContext::new().await?;
+    let ctx = Ctx::root_ctx();
+
+    match command {
+        UserCommand::Create { username } => {
+            let user_c = UserForCreate { username, pwd_clear: read_new_password()? };
+            validate(&user_c)?;
+
+            let id = UserRepository::create(&ctx, &db_context, user_c).await?;
+            println!("user created ({id})");
+        }
+        UserCommand::SetPassword { username } => {
+            let user = get_user(&ctx, &db_context, &username).await?;
+            let user_c = UserForCreate { username, pwd_clear: read_new_password()? };
+            validate(&user_c)?;
+
+            UserRepository::update_pwd(&ctx, &db_context, user.id, &user_c.pwd_clear).await?;
+            println!("password updated");
+        }
+        UserCommand::Disable { username } => {
+            let user = get_user(&ctx, &db_context, &username).await?;
+            UserRepository::set_disabled(&ctx, &db_context, user.id, true).await?;
+            println!("user disabled");
+        }
+        UserCommand::Enable { username } => {
+            let user = get_user(&ctx, &db_context, &username).await?;
+            UserRepository::set_disabled(&ctx, &db_context, user.id, false).await?;
+            println!("user enabled");
+        }
+        UserCommand::GrantAdmin { username } => {
+            let user = get_user(&ctx, &db_context, &username).await?;
+            UserRepository::set_admin(&ctx, &db_context, user.id, true).await?;
+            println!("admin granted");
+        }
+        UserCommand::RevokeAdmin { username } => {
+            let user = get_user(&ctx, &db_context, &username).await?;
+            UserRepository::set_admin(&ctx, &db_context, user.id, false).await?;
+            println!("admin revoked");
+        }
+    }
+
+    Ok(())
+}
+
+pub fn exec_keys(command: KeysCommand) -> Result<()> {
+    match command {
+        KeysCommand::Generate { bytes } => {
+            let mut key = vec![0u8; bytes];
+            rand::thread_rng().fill_bytes(&mut key);
+
+            println!("{}", b64u_encode(key));
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn exec_token(command: TokenCommand) -> Result<()> {
+    match command {
+        TokenCommand::Inspect { token } => {
+            let token: Token = token.trim().parse()?;
+
+            println!("identifier  {}", token.identifier);
+
+            let expiration = match parse_utc(&token.expiration) {
+                Ok(exp) if exp < now_utc() => "expired",
+                Ok(_) => "not expired",
+                Err(_) => "invalid date",
+            };
+            println!("expiration  {} ({expiration})", token.expiration);
+
+            // The signature is salted per user.
+            let db_context = DbContext::new().await?;
+            let user: Option<UserForAuth> =
+                UserRepository::first_by_username(&Ctx::root_ctx(), &db_context, &token.identifier)
+                    .await?;
+            let signature = match user.map(|user| validate_web_token(&token, user.token_salt)) {
+                None => "unknown, user not found",
+                Some(Ok(())) | Some(Err(token::Error::Expired)) => "valid",
+                Some(Err(token::Error::SignatureNotMatching)) => "NOT VALID",
+                Some(Err(ex)) => return Err(ex.into()),
+            };
+            println!("signature   {signature}");
+        }
+    }
+
+    Ok(())
+}
+
+// endregion: -- Exec
+
+fn validate(value: &impl Validate) -> Result<()> {
+    value.validate().map_err(|errors| {
+        let violations = errors
+            .field_errors()
+            .into_iter()
+            .flat_map(|(field, errors)| errors.iter().map(move |e| format!("{field}: {}", e.code)))
+            .collect();
+
+        Error::ValidationFail(violations)
+    })
+}
+
+async fn get_user(ctx: &Ctx, db_context: &DbContext, username: &str) -> Result<UserForAuth> {
+    UserRepository::first_by_username(ctx, db_context, username)
+        .await?
+        .ok_or_else(|| Error::UserNotFound { username: username.to_string() })
+}
+
+/// Prompted twice on a terminal, otherwise the first line of stdin.
+fn read_new_password() -> Result<String> {
+    if !io::stdin().is_terminal() {
+        let mut line = String::new();
+        io::stdin().lock().read_line(&mut line)?;
+        return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+    }
+
+    let pwd = rpassword::prompt_password("Password: ")?;
+    if rpassword::prompt_password("Confirm password: ")? != pwd {
+        return Err(Error::PasswordsNotMatching);
+    }
+
+    Ok(pwd)
+}
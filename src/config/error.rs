@@ -6,6 +6,14 @@ pub type Result<T> = core::result::Result<T, Error>;
 pub enum Error {
     ConfigMissingEnv(&'static str),
     ConfigInvalidFormat(&'static str),
+    ConfigDevOnly(&'static str),
+    ConfigInvalidValue { name: &'static str, reason: &'static str },
+    ConfigFileFail { path: String, cause: String },
+    /// Not a scalar, or not matching any setting.
+    ConfigInvalidFileKey(String),
+
+    /// All the errors found while loading.
+    ConfigInvalid(Vec<Error>),
 }
 
 impl core::fmt::Display for Error {
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::str::FromStr;
+use crate::config::{Error, Result};
+use crate::utils::base64_utils::b64u_decode;
+
+const CONFIG_FILE_ENV: &str = "SERVICE_CONFIG_FILE";
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+/// Settings are named after their env var, e.g. `SERVICE_DB_URL`, and
+/// resolved in order (the last one set wins):
+/// - the default, if any
+/// - the config file key, lowercase without prefix, e.g. `db_url`
+/// - the env var, e.g. `SERVICE_DB_URL`
+/// - the content of the file named by `SERVICE_DB_URL_FILE`, for secrets
+///
+/// Errors are collected, so they can all be reported at once.
+pub(super) struct Loader {
+    file_values: HashMap<String, String>,
+    errors: Vec<Error>,
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        let mut loader = Self { file_values: HashMap::new(), errors: Vec::new() };
+
+        let (path, required) = match env::var(CONFIG_FILE_ENV) {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => loader.load_file(&path, &content),
+            Err(_) if !required => {}
+            Err(ex) => loader.errors.push(Error::ConfigFileFail { path, cause: ex.to_string() }),
+        }
+
+        loader
+    }
+
+    fn load_file(&mut self, path: &str, content: &str) {
+        let table = match content.parse::<toml::Table>() {
+            Ok(table) => table,
+            Err(ex) => {
+                let cause = ex.message().to_string();
+                self.errors.push(Error::ConfigFileFail { path: path.to_string(), cause });
+                return;
+            }
+        };
+
+        for (key, value) in table {
+            let value = match value {
+                toml::Value::String(value) => value,
+                toml::Value::Integer(_) | toml::Value::Float(_) | toml::Value::Boolean(_) => {
+                    value.to_string()
+                }
+                _ => {
+                    self.errors.push(Error::ConfigInvalidFileKey(key));
+                    continue;
+                }
+            };
+            self.file_values.insert(key, value);
+        }
+    }
+
+    /// Reports the invalid settings, and the config file keys matching none.
+    pub fn finish(mut self) -> Result<()> {
+        let mut unknown_keys: Vec<String> = self.file_values.into_keys().collect();
+        unknown_keys.sort();
+        self.errors.extend(unknown_keys.into_iter().map(Error::ConfigInvalidFileKey));
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ConfigInvalid(self.errors))
+        }
+    }
+
+    pub fn push_error(&mut self, error: Error) {
+        self.errors.push(error);
+    }
+
+    // region: -- Getters
+
+    pub fn get_opt(&mut self, name: &'static str) -> Option<String> {
+        // Taken from the file values even when overridden, the leftovers are unknown keys.
+        let file_value = self.file_values.remove(&file_key(name));
+
+        let secret_var = format!("{name}_FILE");
+        if let Some(path) = non_empty_env(&secret_var) {
+            return match fs::read_to_string(&path) {
+                Ok(content) => Some(content.trim_end().to_string()),
+                Err(ex) => {
+                    self.errors.push(Error::ConfigFileFail { path, cause: ex.to_string() });
+                    None
+                }
+            };
+        }
+
+        non_empty_env(name).or(file_value)
+    }
+
+    /// The `Default` value is only a placeholder, the loading fails when missing.
+    pub fn get<T: FromStr + Default>(&mut self, name: &'static str) -> T {
+        let Some(value) = self.get_opt(name) else {
+            self.errors.push(Error::ConfigMissingEnv(name));
+            return T::default();
+        };
+
+        value.parse().unwrap_or_else(|_| {
+            self.errors.push(Error::ConfigInvalidFormat(name));
+            T::default()
+        })
+    }
+
+    pub fn get_parse_or<T: FromStr>(&mut self, name: &'static str, default: T) -> T {
+        self.get_parse_with(name, default, |value| value.parse().ok())
+    }
+
+    pub fn get_parse_with<T>(
+        &mut self,
+        name: &'static str,
+        default: T,
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> T {
+        let Some(value) = self.get_opt(name) else {
+            return default;
+        };
+
+        parse(&value).unwrap_or_else(|| {
+            self.errors.push(Error::ConfigInvalidFormat(name));
+            default
+        })
+    }
+
+    pub fn get_b64u_as_u8s(&mut self, name: &'static str) -> Vec<u8> {
+        let value: String = self.get(name);
+        if value.is_empty() {
+            return Vec::new();
+        }
+
+        b64u_decode(&value).unwrap_or_else(|_| {
+            self.errors.push(Error::ConfigInvalidFormat(name));
+            Vec::new()
+        })
+    }
+
+    // endregion: -- Getters
+}
+
+fn file_key(name: &str) -> String {
+    name.trim_start_matches("SERVICE_").to_lowercase()
+}
+
+/// An empty env var counts as unset, e.g. `SERVICE_X=` in a compose file.
+fn non_empty_env(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn empty_loader() -> Loader {
+        Loader { file_values: HashMap::new(), errors: Vec::new() }
+    }
+
+    #[test]
+    fn test_loader_file_values_and_errors() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_content = r#"
+            test_loader_str = "from file"
+            test_loader_num = 3
+            test_loader_bad = "not a number"
+            nested = { a = 1 }
+            unknown_key = true
+        "#;
+        let mut loader = empty_loader();
+        loader.load_file("fx.toml", fx_content);
+
+        // -- Exec
+        let str_value = loader.get_opt("SERVICE_TEST_LOADER_STR");
+        let num_value: u32 = loader.get_parse_or("SERVICE_TEST_LOADER_NUM", 0);
+        let bad_num: u32 = loader.get_parse_or("SERVICE_TEST_LOADER_BAD", 7);
+        let missing: String = loader.get("SERVICE_TEST_LOADER_MISSING");
+        let res = loader.finish();
+
+        // -- Check
+        assert_eq!(str_value.as_deref(), Some("from file"));
+        assert_eq!(num_value, 3);
+        assert_eq!(bad_num, 7, "Should fall back to the default");
+        assert_eq!(missing, "");
+        let Err(Error::ConfigInvalid(errors)) = res else {
+            panic!("Should have collected the errors, got {res:?}");
+        };
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                r#"ConfigInvalidFileKey("nested")"#,
+                r#"ConfigInvalidFormat("SERVICE_TEST_LOADER_BAD")"#,
+                r#"ConfigMissingEnv("SERVICE_TEST_LOADER_MISSING")"#,
+                r#"ConfigInvalidFileKey("unknown_key")"#,
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_loader_secret_file_over_env_over_file() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_secret_path = env::temp_dir().join("webapi_test_loader_secret");
+        fs::write(&fx_secret_path, "from secret file\n")?;
+        env::set_var("SERVICE_TEST_LOADER_SECRET", "from env");
+        env::set_var("SERVICE_TEST_LOADER_SECRET_FILE", &fx_secret_path);
+        env::set_var("SERVICE_TEST_LOADER_ENV", "from env");
+        let mut loader = empty_loader();
+        loader.load_file("fx.toml", "test_loader_secret = \"x\"\ntest_loader_env = \"x\"");
+
+        // -- Exec
+        let secret = loader.get_opt("SERVICE_TEST_LOADER_SECRET");
+        let env_value = loader.get_opt("SERVICE_TEST_LOADER_ENV");
+
+        // -- Check
+        assert_eq!(secret.as_deref(), Some("from secret file"));
+        assert_eq!(env_value.as_deref(), Some("from env"));
+        loader.finish()?;
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -1,52 +1,328 @@
 mod error;
+mod loader;
 
-use std::env;
+use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::OnceLock;
-use crate::utils::base64_utils::b64u_decode;
+use std::time::Duration;
+use axum::http::HeaderValue;
+use tower_cookies::cookie::SameSite;
+use crate::event::EventBusBackend;
+use crate::log::RequestLogBackend;
+use crate::rate_limit::{Quota, RateLimitBackend};
+use self::loader::Loader;
 pub use self::error::{Error, Result};
 
-pub fn config() -> &'static Config {
-    static INSTANCE: OnceLock<Config> = OnceLock::new();
+/// Under it, a HMAC key is refused.
+const KEY_MIN_LEN: usize = 32;
 
-    INSTANCE.get_or_init(|| {
-        Config::load_from_env().unwrap_or_else(|ex| {
-            panic!("FATAL - WHILE LOADING CONF - Cause: {ex:?}")
-        })
+/// Only the resources of the service itself, and never in a frame.
+const DEFAULT_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'self'; object-src 'none'; base-uri 'self'; frame-ancestors 'none'";
+
+static INSTANCE: OnceLock<Config> = OnceLock::new();
+
+/// Loads the config once, reporting all the invalid settings together.
+pub fn init() -> Result<&'static Config> {
+    if let Some(config) = INSTANCE.get() {
+        return Ok(config);
+    }
+
+    let config = Config::load()?;
+
+    Ok(INSTANCE.get_or_init(|| config))
+}
+
+pub fn config() -> &'static Config {
+    init().unwrap_or_else(|ex| {
+        panic!("FATAL - WHILE LOADING CONF - Cause: {ex:?}")
     })
 }
 
 #[allow(non_snake_case)]
 pub struct Config {
+    pub RUN_MODE: RunMode,
+    /// Dev only, recreate and seed the database on `serve`.
+    pub DEV_RESET_DB: bool,
+    /// Dev only, superuser connection used to recreate the database.
+    pub DEV_ROOT_DB_URL: Option<String>,
+
     pub PWD_KEY: Vec<u8>,
     pub TOKEN_KEY: Vec<u8>,
     pub TOKEN_DURATION_SEC: f64,
+    pub COOKIE_SECURE: bool,
+    pub COOKIE_SAME_SITE: SameSite,
 
     pub DB_URL: String,
+    pub DB_MAX_CONNECTIONS: u32,
+    /// Kept open even when idle.
+    pub DB_MIN_CONNECTIONS: u32,
+    pub DB_ACQUIRE_TIMEOUT_SEC: f64,
+    /// 0 to keep the idle connections.
+    pub DB_IDLE_TIMEOUT_SEC: f64,
+    /// 0 for no timeout. Not applied to the migrations.
+    pub DB_STATEMENT_TIMEOUT_SEC: f64,
+    /// How long to retry at startup while the database is not reachable.
+    pub DB_CONNECT_TIMEOUT_SEC: f64,
+    pub DB_MIGRATE_ON_START: bool,
     pub WEB_FOLDER: String,
+    pub BIND_ADDR: SocketAddr,
+    /// The origins allowed to call the API from a browser, comma separated. Empty for the same origin only.
+    pub CORS_ALLOWED_ORIGINS: Vec<String>,
+    /// Whether the cross-origin calls may send the auth-token cookie.
+    pub CORS_ALLOW_CREDENTIALS: bool,
+    /// None when `off`.
+    pub CONTENT_SECURITY_POLICY: Option<String>,
+    /// 0 to not send `Strict-Transport-Security`.
+    pub HSTS_MAX_AGE_SEC: u64,
+    /// Request body limit of the routes without their own.
+    pub BODY_LIMIT_BYTES: usize,
+    pub BODY_LIMIT_RPC_BYTES: usize,
+    /// OTLP/HTTP base url the spans are exported to, e.g. `http://localhost:4318`. None to not export.
+    pub OTEL_ENDPOINT: Option<String>,
+    pub OTEL_SERVICE_NAME: String,
+    pub REQUEST_LOG: RequestLogBackend,
+    pub REQUEST_LOG_DIR: String,
+    /// Size of a request log file before it is rotated.
+    pub REQUEST_LOG_MAX_BYTES: u64,
+    /// Rotated request log files kept, the oldest are deleted.
+    pub REQUEST_LOG_MAX_FILES: usize,
+    /// Added to the default denylist of the fields redacted from the logs, comma separated.
+    pub LOG_REDACT_KEYS: Vec<String>,
+    pub RATE_LIMIT_STORE: RateLimitBackend,
+    /// Per client IP on `/api/login`. None when `off`.
+    pub RATE_LIMIT_LOGIN: Option<Quota>,
+    /// Per user on the RPC calls, over HTTP and the websocket. None when `off`.
+    pub RATE_LIMIT_RPC: Option<Quota>,
+    /// Per user and RPC method, on top of `RATE_LIMIT_RPC`, e.g. `create_task=30/min`, comma separated.
+    pub RATE_LIMIT_RPC_METHODS: Vec<(String, Quota)>,
+
+    pub EVENT_BUS: EventBusBackend,
+    pub JOB_WORKERS: usize,
+    /// Days the done jobs and the finished webhook deliveries are kept, see `job::purge`.
+    pub JOB_HISTORY_KEEP_DAYS: i32,
+    /// Lets the webhooks target loopback, private and link-local addresses.
+    pub WEBHOOK_ALLOW_PRIVATE_TARGETS: bool,
 }
 
 impl Config {
-    fn load_from_env() -> Result<Config> {
-        Ok(Config {
-            PWD_KEY: get_env_b64u_as_u8s("SERVICE_PWD_KEY")?,
-            TOKEN_KEY: get_env_b64u_as_u8s("SERVICE_TOKEN_KEY")?,
-            TOKEN_DURATION_SEC: get_env_parse("SERVICE_TOKEN_DURATION_SEC")?,
-            DB_URL: get_env("SERVICE_DB_URL")?,
-            WEB_FOLDER: get_env("SERVICE_WEB_FOLDER")?,
+    fn load() -> Result<Config> {
+        let mut loader = Loader::new();
+
+        let run_mode = loader.get_parse_or("SERVICE_RUN_MODE", RunMode::Prod);
+        let is_dev = run_mode == RunMode::Dev;
+        let is_prod = run_mode == RunMode::Prod;
+
+        let config = Config {
+            RUN_MODE: run_mode,
+            DEV_RESET_DB: loader.get_parse_or("SERVICE_DEV_RESET_DB", is_dev),
+            DEV_ROOT_DB_URL: loader.get_opt("SERVICE_DEV_ROOT_DB_URL"),
+
+            PWD_KEY: loader.get_b64u_as_u8s("SERVICE_PWD_KEY"),
+            TOKEN_KEY: loader.get_b64u_as_u8s("SERVICE_TOKEN_KEY"),
+            TOKEN_DURATION_SEC: loader.get_parse_or("SERVICE_TOKEN_DURATION_SEC", 1800.),
+            COOKIE_SECURE: loader.get_parse_or("SERVICE_COOKIE_SECURE", is_prod),
+            COOKIE_SAME_SITE: loader.get_parse_with("SERVICE_COOKIE_SAME_SITE", SameSite::Lax, parse_same_site),
+
+            DB_URL: loader.get("SERVICE_DB_URL"),
+            DB_MAX_CONNECTIONS: loader.get_parse_or("SERVICE_DB_MAX_CONNECTIONS", 5),
+            DB_MIN_CONNECTIONS: loader.get_parse_or("SERVICE_DB_MIN_CONNECTIONS", 0),
+            DB_ACQUIRE_TIMEOUT_SEC: loader.get_parse_or("SERVICE_DB_ACQUIRE_TIMEOUT_SEC", 30.),
+            DB_IDLE_TIMEOUT_SEC: loader.get_parse_or("SERVICE_DB_IDLE_TIMEOUT_SEC", 600.),
+            DB_STATEMENT_TIMEOUT_SEC: loader.get_parse_or("SERVICE_DB_STATEMENT_TIMEOUT_SEC", 0.),
+            DB_CONNECT_TIMEOUT_SEC: loader.get_parse_or("SERVICE_DB_CONNECT_TIMEOUT_SEC", 60.),
+            DB_MIGRATE_ON_START: loader.get_parse_or("SERVICE_DB_MIGRATE_ON_START", true),
+            WEB_FOLDER: loader.get_parse_or("SERVICE_WEB_FOLDER", "web-folder/".to_string()),
+            BIND_ADDR: loader.get_parse_or("SERVICE_BIND_ADDR", SocketAddr::from(([0, 0, 0, 0], 8080))),
+            CORS_ALLOWED_ORIGINS: loader.get_parse_with("SERVICE_CORS_ALLOWED_ORIGINS", Vec::new(), parse_list),
+            CORS_ALLOW_CREDENTIALS: loader.get_parse_or("SERVICE_CORS_ALLOW_CREDENTIALS", true),
+            CONTENT_SECURITY_POLICY: loader.get_parse_with(
+                "SERVICE_CONTENT_SECURITY_POLICY",
+                Some(DEFAULT_CONTENT_SECURITY_POLICY.to_string()),
+                |value| Some((value != "off").then(|| value.to_string())),
+            ),
+            HSTS_MAX_AGE_SEC: loader.get_parse_or("SERVICE_HSTS_MAX_AGE_SEC", if is_prod { 31_536_000 } else { 0 }),
+            BODY_LIMIT_BYTES: loader.get_parse_or("SERVICE_BODY_LIMIT_BYTES", 1024 * 1024),
+            BODY_LIMIT_RPC_BYTES: loader.get_parse_or("SERVICE_BODY_LIMIT_RPC_BYTES", 256 * 1024),
+            OTEL_ENDPOINT: loader.get_opt("SERVICE_OTEL_ENDPOINT"),
+            OTEL_SERVICE_NAME: loader.get_parse_or("SERVICE_OTEL_SERVICE_NAME", "webapi".to_string()),
+            REQUEST_LOG: loader.get_parse_or("SERVICE_REQUEST_LOG", RequestLogBackend::Stdout),
+            REQUEST_LOG_DIR: loader.get_parse_or("SERVICE_REQUEST_LOG_DIR", "logs/".to_string()),
+            REQUEST_LOG_MAX_BYTES: loader.get_parse_or("SERVICE_REQUEST_LOG_MAX_BYTES", 10 * 1024 * 1024),
+            REQUEST_LOG_MAX_FILES: loader.get_parse_or("SERVICE_REQUEST_LOG_MAX_FILES", 5),
+            LOG_REDACT_KEYS: loader.get_parse_with("SERVICE_LOG_REDACT_KEYS", Vec::new(), parse_list),
+            RATE_LIMIT_STORE: loader.get_parse_or("SERVICE_RATE_LIMIT_STORE", RateLimitBackend::Memory),
+            RATE_LIMIT_LOGIN: loader.get_parse_with("SERVICE_RATE_LIMIT_LOGIN", Some(Quota::new(10, Duration::from_secs(60))), parse_quota),
+            RATE_LIMIT_RPC: loader.get_parse_with("SERVICE_RATE_LIMIT_RPC", Some(Quota::new(300, Duration::from_secs(60))), parse_quota),
+            RATE_LIMIT_RPC_METHODS: loader.get_parse_with("SERVICE_RATE_LIMIT_RPC_METHODS", Vec::new(), parse_method_quotas),
+
+            EVENT_BUS: loader.get_parse_or("SERVICE_EVENT_BUS", EventBusBackend::Local),
+            JOB_WORKERS: loader.get_parse_or("SERVICE_JOB_WORKERS", 4),
+            JOB_HISTORY_KEEP_DAYS: loader.get_parse_or("SERVICE_JOB_HISTORY_KEEP_DAYS", 30),
+            WEBHOOK_ALLOW_PRIVATE_TARGETS: loader.get_parse_or("SERVICE_WEBHOOK_ALLOW_PRIVATE_TARGETS", false),
+        };
+        config.check(&mut loader);
+        loader.finish()?;
+
+        Ok(config)
+    }
+
+    /// The checks between settings, or on their values.
+    fn check(&self, loader: &mut Loader) {
+        // Production refuses to start with a dev only setting, rather than ignoring it.
+        if self.RUN_MODE == RunMode::Prod {
+            if self.DEV_RESET_DB {
+                loader.push_error(Error::ConfigDevOnly("SERVICE_DEV_RESET_DB"));
+            }
+            if self.DEV_ROOT_DB_URL.is_some() {
+                loader.push_error(Error::ConfigDevOnly("SERVICE_DEV_ROOT_DB_URL"));
+            }
+        }
+
+        let mut invalid = |name, reason| loader.push_error(Error::ConfigInvalidValue { name, reason });
+        // Empty when missing, already reported.
+        if !self.PWD_KEY.is_empty() && self.PWD_KEY.len() < KEY_MIN_LEN {
+            invalid("SERVICE_PWD_KEY", "shorter than 32 bytes");
+        }
+        if !self.TOKEN_KEY.is_empty() && self.TOKEN_KEY.len() < KEY_MIN_LEN {
+            invalid("SERVICE_TOKEN_KEY", "shorter than 32 bytes");
+        }
+        if self.TOKEN_DURATION_SEC <= 0. {
+            invalid("SERVICE_TOKEN_DURATION_SEC", "not positive");
+        }
+        if self.DB_MAX_CONNECTIONS == 0 {
+            invalid("SERVICE_DB_MAX_CONNECTIONS", "zero");
+        }
+        if self.DB_MIN_CONNECTIONS > self.DB_MAX_CONNECTIONS {
+            invalid("SERVICE_DB_MIN_CONNECTIONS", "greater than SERVICE_DB_MAX_CONNECTIONS");
+        }
+        if !self.DB_ACQUIRE_TIMEOUT_SEC.is_finite() || self.DB_ACQUIRE_TIMEOUT_SEC <= 0. {
+            invalid("SERVICE_DB_ACQUIRE_TIMEOUT_SEC", "not positive");
+        }
+        for (name, value) in [
+            ("SERVICE_DB_IDLE_TIMEOUT_SEC", self.DB_IDLE_TIMEOUT_SEC),
+            ("SERVICE_DB_STATEMENT_TIMEOUT_SEC", self.DB_STATEMENT_TIMEOUT_SEC),
+            ("SERVICE_DB_CONNECT_TIMEOUT_SEC", self.DB_CONNECT_TIMEOUT_SEC),
+        ] {
+            if !value.is_finite() || value < 0. {
+                invalid(name, "negative");
+            }
+        }
+        if self.JOB_HISTORY_KEEP_DAYS <= 0 {
+            invalid("SERVICE_JOB_HISTORY_KEEP_DAYS", "not positive");
+        }
+        if self.REQUEST_LOG_MAX_BYTES == 0 {
+            invalid("SERVICE_REQUEST_LOG_MAX_BYTES", "zero");
+        }
+        if self.BODY_LIMIT_BYTES == 0 {
+            invalid("SERVICE_BODY_LIMIT_BYTES", "zero");
+        }
+        if self.BODY_LIMIT_RPC_BYTES == 0 {
+            invalid("SERVICE_BODY_LIMIT_RPC_BYTES", "zero");
+        }
+        // An origin is sent back as is, e.g. `https://app.example.com`, without a path.
+        let is_origin = |origin: &String| {
+            (origin.starts_with("https://") || origin.starts_with("http://"))
+                && !origin.ends_with('/')
+                && HeaderValue::from_str(origin).is_ok()
+        };
+        if !self.CORS_ALLOWED_ORIGINS.iter().all(|origin| origin == "*" || is_origin(origin)) {
+            invalid("SERVICE_CORS_ALLOWED_ORIGINS", "not a list of origins, or `*`");
+        }
+        // Browsers refuse the credentials of a wildcard origin.
+        if self.CORS_ALLOW_CREDENTIALS && self.CORS_ALLOWED_ORIGINS.iter().any(|origin| origin == "*") {
+            invalid("SERVICE_CORS_ALLOWED_ORIGINS", "`*` requires SERVICE_CORS_ALLOW_CREDENTIALS=false");
+        }
+        if let Some(csp) = &self.CONTENT_SECURITY_POLICY {
+            if HeaderValue::from_str(csp).is_err() {
+                invalid("SERVICE_CONTENT_SECURITY_POLICY", "not a valid header value");
+            }
+        }
+        // Browsers drop the `SameSite=None` cookies which are not `Secure`.
+        if self.COOKIE_SAME_SITE == SameSite::None && !self.COOKIE_SECURE {
+            invalid("SERVICE_COOKIE_SAME_SITE", "none requires SERVICE_COOKIE_SECURE");
+        }
+    }
+}
+
+/// Comma separated, the empty items are skipped.
+fn parse_list(value: &str) -> Option<Vec<String>> {
+    Some(value.split(',').map(|item| item.trim().to_string()).filter(|item| !item.is_empty()).collect())
+}
+
+fn parse_quota(value: &str) -> Option<Option<Quota>> {
+    match value {
+        "off" => Some(None),
+        _ => value.parse().ok().map(Some),
+    }
+}
+
+fn parse_method_quotas(value: &str) -> Option<Vec<(String, Quota)>> {
+    value
+        .split(',')
+        .filter(|entry| !entry.trim().is_empty())
+        .map(|entry| {
+            let (method, quota) = entry.split_once('=')?;
+            Some((method.trim().to_string(), quota.parse().ok()?))
         })
+        .collect()
+}
+
+fn parse_same_site(value: &str) -> Option<SameSite> {
+    match value {
+        "strict" => Some(SameSite::Strict),
+        "lax" => Some(SameSite::Lax),
+        "none" => Some(SameSite::None),
+        _ => None,
     }
 }
 
-fn get_env(name: &'static str) -> Result<String> {
-    env::var(name).map_err(|_| Error::ConfigMissingEnv(name))
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunMode {
+    /// Local development, `serve` recreates and seeds the database.
+    Dev,
+    /// Automated tests, only the test setup resets the database.
+    Test,
+    Prod,
 }
 
-fn get_env_b64u_as_u8s(name: &'static str) -> Result<Vec<u8>> {
-    b64u_decode(&get_env(name)?).map_err(|_| Error::ConfigInvalidFormat(name))
+impl RunMode {
+    /// Whether the database may be dropped and seeded with the demo data.
+    /// Test included, `_dev_utils::init_test` resets the database of the
+    /// tests, while `serve` only resets it in Dev (see `DEV_RESET_DB`).
+    pub fn allows_db_reset(&self) -> bool {
+        matches!(self, RunMode::Dev | RunMode::Test)
+    }
 }
 
-fn get_env_parse<T: FromStr>(name: &'static str) -> Result<T> {
-    let value = get_env(name)?;
-    value.parse::<T>().map_err(|_| Error::ConfigInvalidFormat(name))
+impl FromStr for RunMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "dev" => Ok(RunMode::Dev),
+            "test" => Ok(RunMode::Test),
+            "prod" => Ok(RunMode::Prod),
+            _ => Err(Error::ConfigInvalidFormat("SERVICE_RUN_MODE")),
+        }
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_run_mode_allows_db_reset() -> Result<()> {
+        // -- Exec & Check
+        assert!(RunMode::Dev.allows_db_reset());
+        assert!(RunMode::Test.allows_db_reset(), "The test setup resets the database");
+        assert!(!RunMode::Prod.allows_db_reset());
+        assert_eq!("prod".parse::<RunMode>()?, RunMode::Prod);
+
+        Ok(())
+    }
 }
+// endregion: -- Tests
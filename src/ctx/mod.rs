@@ -5,24 +5,46 @@ pub use self::error::{Error, Result};
 #[derive(Clone, Debug)]
 pub struct Ctx {
     user_id: i64,
+    is_admin: bool,
+    /// The request acting, recorded in the audit trail.
+    req_id: Option<String>,
 }
 
 impl Ctx {
+    /// The system, e.g. the CLI and the workers, is an admin.
     pub fn root_ctx() -> Self {
-        Ctx { user_id: 0 }
+        Ctx { user_id: 0, is_admin: true, req_id: None }
     }
 
     pub fn new(user_id: i64) -> Result<Self> {
         if user_id == 0 {
             Err(Error::CtxCannotNewRootCtx)
         } else {
-            Ok(Self { user_id })
+            Ok(Self { user_id, is_admin: false, req_id: None })
         }
     }
+
+    pub fn with_admin(mut self, is_admin: bool) -> Self {
+        self.is_admin = is_admin;
+        self
+    }
+
+    pub fn with_req_id(mut self, req_id: impl Into<String>) -> Self {
+        self.req_id = Some(req_id.into());
+        self
+    }
 }
 
 impl Ctx {
     pub fn user_id(&self) -> i64 {
         self.user_id
     }
-}
\ No newline at end of file
+
+    pub fn is_admin(&self) -> bool {
+        self.is_admin
+    }
+
+    pub fn req_id(&self) -> Option<&str> {
+        self.req_id.as_deref()
+    }
+}
@@ -1,13 +1,25 @@
 use std::fmt::Formatter;
 use derive_more::From;
-use crate::model;
+use crate::{cli, config, job, log, model, telemetry};
 
 pub type Result<T> = core::result::Result<T, Error>;
 
 #[derive(Debug, From)]
 pub enum Error {
+    #[from]
+    Config(config::Error),
+    #[from]
+    Cli(cli::Error),
     #[from]
     Model(model::Error),
+    #[from]
+    Migration(model::migration::Error),
+    #[from]
+    Telemetry(telemetry::Error),
+    #[from]
+    Log(log::Error),
+    #[from]
+    Job(job::Error),
 
     FailToCreatePool { msg: String },
 }
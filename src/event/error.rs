@@ -0,0 +1,22 @@
+use std::fmt::Formatter;
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+pub enum Error {
+    UnknownBusBackend(String),
+
+    ListenerFailToConnect(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+    PublishFail(String),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
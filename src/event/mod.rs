@@ -0,0 +1,275 @@
+mod error;
+
+pub use self::error::{Error, Result};
+
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use tokio::sync::broadcast;
+use tracing::{debug, warn};
+use uuid::Uuid;
+use crate::config::config;
+use crate::ctx::Ctx;
+use crate::model::task::Task;
+
+/// Every `Event::name`, what webhooks can subscribe to.
+pub const EVENT_TYPES: &[&str] = &["task.created", "task.updated", "task.deleted"];
+
+const EVENT_BUS_CAPACITY: usize = 256;
+const PG_EVENT_CHANNEL: &str = "webapi_events";
+
+// region: -- Event
+
+// Only task events for now, other entities will follow.
+#[allow(clippy::enum_variant_names)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Event {
+    #[serde(rename = "task.created")]
+    TaskCreated { actor_id: i64, task: Task },
+    #[serde(rename = "task.updated")]
+    TaskUpdated { actor_id: i64, task: Task },
+    #[serde(rename = "task.deleted")]
+    TaskDeleted { actor_id: i64, task: Task },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::TaskCreated { .. } => "task.created",
+            Event::TaskUpdated { .. } => "task.updated",
+            Event::TaskDeleted { .. } => "task.deleted",
+        }
+    }
+
+    pub fn actor_id(&self) -> i64 {
+        match self {
+            Event::TaskCreated { actor_id, .. }
+            | Event::TaskUpdated { actor_id, .. }
+            | Event::TaskDeleted { actor_id, .. } => *actor_id,
+        }
+    }
+
+    /// Same visibility as the repositories: tasks are not scoped
+    /// by user, so every authenticated `Ctx` may see their events.
+    pub fn is_visible_to(&self, _ctx: &Ctx) -> bool {
+        match self {
+            Event::TaskCreated { .. }
+            | Event::TaskUpdated { .. }
+            | Event::TaskDeleted { .. } => true,
+        }
+    }
+}
+
+/// What actually travels on the bus, the `id` lets consumers
+/// (SSE clients, webhook deliveries) deduplicate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+// endregion: -- Event
+
+// region: -- EventBus
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventBusBackend {
+    /// In-process only, events are not seen by other instances.
+    Local,
+    /// Fanned out through Postgres `LISTEN/NOTIFY`, for multi-instance deployments.
+    Postgres,
+}
+
+impl FromStr for EventBusBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "local" => Ok(EventBusBackend::Local),
+            "postgres" => Ok(EventBusBackend::Postgres),
+            other => Err(Error::UnknownBusBackend(other.to_string())),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<EventEnvelope>,
+    notify_db: Option<PgPool>,
+}
+
+impl EventBus {
+    pub async fn new(db: &PgPool) -> Result<Self> {
+        match config().EVENT_BUS {
+            EventBusBackend::Local => Ok(Self::local()),
+            EventBusBackend::Postgres => Self::postgres(db.clone()).await,
+        }
+    }
+
+    pub fn local() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+
+        Self { tx, notify_db: None }
+    }
+
+    /// Events are published with `pg_notify` and only reach the local
+    /// subscribers once they come back through the listener, so every
+    /// instance (this one included) sees them exactly once.
+    pub async fn postgres(db: PgPool) -> Result<Self> {
+        let (tx, _) = broadcast::channel(EVENT_BUS_CAPACITY);
+
+        // Dedicated connection, so the listener does not hold one from the pool.
+        let mut listener = PgListener::connect(&config().DB_URL)
+            .await
+            .map_err(Error::ListenerFailToConnect)?;
+        listener
+            .listen(PG_EVENT_CHANNEL)
+            .await
+            .map_err(Error::ListenerFailToConnect)?;
+
+        let listener_tx = tx.clone();
+        tokio::spawn(async move {
+            loop {
+                match listener.recv().await {
+                    Ok(notification) => match serde_json::from_str::<EventEnvelope>(notification.payload()) {
+                        Ok(event) => {
+                            let _ = listener_tx.send(event);
+                        }
+                        Err(ex) => warn!("{:<12} - invalid event payload: {ex}", "EVENT_BUS"),
+                    },
+                    // The listener reconnects by itself on the next `recv`.
+                    Err(ex) => warn!("{:<12} - listener error: {ex}", "EVENT_BUS"),
+                }
+            }
+        });
+
+        Ok(Self { tx, notify_db: Some(db) })
+    }
+
+    /// Publishing happens after the change is committed,
+    /// so a failure is logged rather than failing the request.
+    pub async fn publish(&self, event: Event) {
+        debug!("{:<12} - publish {}", "EVENT_BUS", event.name());
+
+        let envelope = EventEnvelope { id: Uuid::new_v4(), event };
+        if let Err(ex) = self._publish(envelope).await {
+            warn!("{:<12} - fail to publish: {ex}", "EVENT_BUS");
+        }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.tx.subscribe()
+    }
+
+    async fn _publish(&self, envelope: EventEnvelope) -> Result<()> {
+        match &self.notify_db {
+            None => {
+                // No subscribers is not an error.
+                let _ = self.tx.send(envelope);
+            }
+            Some(db) => {
+                let payload = serde_json::to_string(&envelope)
+                    .map_err(|ex| Error::PublishFail(ex.to_string()))?;
+                sqlx::query("SELECT pg_notify($1, $2)")
+                    .bind(PG_EVENT_CHANNEL)
+                    .bind(payload)
+                    .execute(db)
+                    .await
+                    .map_err(|ex| Error::PublishFail(ex.to_string()))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// endregion: -- EventBus
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use serial_test::serial;
+    use crate::_dev_utils;
+    use crate::model::task::{TaskForCreate, TaskRepository};
+
+    #[serial]
+    #[tokio::test]
+    async fn test_task_create_publishes_event() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let fx_title = "test_task_create_publishes_event title";
+        let mut rx = db_context.events().subscribe();
+
+        // -- Exec
+        let id = TaskRepository::create(
+            &ctx,
+            &db_context,
+            TaskForCreate { title: fx_title.to_string() },
+        ).await?;
+        TaskRepository::delete(&ctx, &db_context, id).await?;
+
+        // -- Check
+        let Event::TaskCreated { actor_id, task } = rx.recv().await?.event else {
+            panic!("Should have received a `task.created` event first");
+        };
+        assert_eq!(actor_id, 1000);
+        assert_eq!(task.title, fx_title);
+
+        let event = rx.recv().await?.event;
+        assert_eq!(event.name(), "task.deleted");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_postgres_bus_roundtrip() -> Result<()> {
+        // -- Setup & Fixtures
+        _dev_utils::init_dev().await;
+        let bus = EventBus::postgres(PgPool::connect(&config().DB_URL).await?).await?;
+        let mut rx = bus.subscribe();
+        let fx_event = Event::TaskDeleted {
+            actor_id: 1000,
+            task: Task { id: 1001, title: "title".to_string() },
+        };
+
+        // -- Exec
+        bus.publish(fx_event).await;
+
+        // -- Check
+        let event = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv()).await??.event;
+        assert_eq!(event.name(), "task.deleted");
+        assert_eq!(event.actor_id(), 1000);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_event_json_type_tag() -> Result<()> {
+        // -- Fixtures
+        let fx_envelope = EventEnvelope {
+            id: Uuid::new_v4(),
+            event: Event::TaskUpdated {
+                actor_id: 1000,
+                task: Task { id: 1001, title: "title".to_string() },
+            },
+        };
+
+        // -- Exec
+        let json = serde_json::to_value(&fx_envelope)?;
+
+        // -- Check
+        assert_eq!(json["id"], fx_envelope.id.to_string());
+        assert_eq!(json["type"], "task.updated");
+        assert_eq!(json["task"]["id"], 1001);
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,30 @@
+use std::fmt::Formatter;
+use derive_more::From;
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+use crate::model;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize, From)]
+pub enum Error {
+    #[from]
+    Model(model::Error),
+
+    PayloadInvalid { kind: String, message: String },
+    /// Returned by `JobPayload::run` implementations, the job is retried.
+    JobFail(String),
+    JobPanicked { kind: String },
+
+    #[from]
+    SerdeJson(#[serde_as(as = "DisplayFromStr")] serde_json::Error),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
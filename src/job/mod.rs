@@ -0,0 +1,246 @@
+mod error;
+pub mod purge;
+
+pub use self::error::{Error, Result};
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use futures_util::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use time::OffsetDateTime;
+use tracing::{debug, info, warn};
+use crate::ctx::Ctx;
+use crate::model::job::{Job, JobForCreate, JobRepository};
+use crate::model::DbContext;
+use crate::utils::task_utils::{spawn_worker, WorkerHandle};
+use crate::utils::time_utils::{backoff_delay, now_utc};
+
+/// A typed job, stored as JSON under its `KIND`.
+///
+/// Jobs run with the root `Ctx` and may run more than once
+/// (retries, expired leases), so `run` should be idempotent.
+#[async_trait]
+pub trait JobPayload: Serialize + DeserializeOwned + Send + 'static {
+    const KIND: &'static str;
+    const MAX_ATTEMPTS: i32 = 5;
+
+    async fn run(self, ctx: &Ctx, db_context: &DbContext) -> Result<()>;
+}
+
+pub async fn enqueue<J: JobPayload>(ctx: &Ctx, db_context: &DbContext, job: &J) -> Result<i64> {
+    enqueue_at(ctx, db_context, job, now_utc()).await
+}
+
+pub async fn enqueue_at<J: JobPayload>(
+    ctx: &Ctx,
+    db_context: &DbContext,
+    job: &J,
+    run_at: OffsetDateTime,
+) -> Result<i64> {
+    let job_c = JobForCreate {
+        kind: J::KIND.to_string(),
+        payload: serde_json::to_value(job)?,
+        max_attempts: J::MAX_ATTEMPTS,
+        run_at,
+    };
+
+    let id = JobRepository::create(ctx, db_context, job_c).await?;
+    debug!("{:<12} - enqueued {} ({id})", "JOB", J::KIND);
+
+    Ok(id)
+}
+
+// region: -- JobRegistry
+
+type JobFn = Arc<dyn Fn(Value, DbContext) -> BoxFuture<'static, Result<()>> + Send + Sync>;
+
+/// The job kinds this instance can run. Workers only claim these, so an
+/// older instance leaves the jobs it does not know to the newer ones.
+#[derive(Default, Clone)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, JobFn>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<J: JobPayload>(mut self) -> Self {
+        let handler: JobFn = Arc::new(|payload, db_context| {
+            Box::pin(async move {
+                let job: J = serde_json::from_value(payload).map_err(|ex| Error::PayloadInvalid {
+                    kind: J::KIND.to_string(),
+                    message: ex.to_string(),
+                })?;
+
+                job.run(&Ctx::root_ctx(), &db_context).await
+            })
+        });
+        self.handlers.insert(J::KIND, handler);
+
+        self
+    }
+
+    fn kinds(&self) -> Vec<String> {
+        self.handlers.keys().map(|kind| kind.to_string()).collect()
+    }
+}
+
+// endregion: -- JobRegistry
+
+// region: -- Worker Pool
+
+#[derive(Debug, Clone)]
+pub struct WorkerSettings {
+    pub concurrency: usize,
+    pub poll_interval: Duration,
+    /// A job running longer than this is considered lost and picked again.
+    pub lease: Duration,
+    /// Delay before the first retry, doubled on each following one.
+    pub retry_base: Duration,
+    pub retry_max: Duration,
+}
+
+impl Default for WorkerSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            poll_interval: Duration::from_secs(2),
+            lease: Duration::from_secs(300),
+            retry_base: Duration::from_secs(5),
+            retry_max: Duration::from_secs(3600),
+        }
+    }
+}
+
+pub fn start(db_context: DbContext, registry: JobRegistry, settings: WorkerSettings) -> Vec<WorkerHandle> {
+    if registry.handlers.is_empty() {
+        info!("{:<12} - no job registered, workers not started", "JOB");
+        return Vec::new();
+    }
+
+    info!("{:<12} - starting {} worker(s)", "JOB", settings.concurrency);
+
+    let registry = Arc::new(registry);
+    (0..settings.concurrency)
+        .map(|_| spawn_worker("job", run_worker(db_context.clone(), registry.clone(), settings.clone())))
+        .collect()
+}
+
+async fn run_worker(db_context: DbContext, registry: Arc<JobRegistry>, settings: WorkerSettings) {
+    loop {
+        match run_next(&db_context, &registry, &settings).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(ex) => warn!("{:<12} - worker error: {ex}", "JOB"),
+        }
+
+        tokio::time::sleep(settings.poll_interval).await;
+    }
+}
+
+/// Claims and runs one due job, returns `false` when there was none.
+pub async fn run_next(
+    db_context: &DbContext,
+    registry: &JobRegistry,
+    settings: &WorkerSettings,
+) -> Result<bool> {
+    let root_ctx = Ctx::root_ctx();
+
+    let Some(job) = JobRepository::claim_next(
+        &root_ctx,
+        db_context,
+        &registry.kinds(),
+        settings.lease.as_secs_f64(),
+    ).await? else {
+        return Ok(false);
+    };
+
+    let Job { id, ref kind, ref payload, attempts, max_attempts, .. } = job;
+    debug!("{:<12} - run {kind} ({id}), attempt {attempts}", "JOB");
+
+    let Some(handler) = registry.handlers.get(kind.as_str()) else {
+        // Claimed by kind, should not happen.
+        return Ok(false);
+    };
+
+    // On its own task, so a panicking job is only a failed attempt.
+    let res = tokio::spawn(handler(payload.clone(), db_context.clone()))
+        .await
+        .unwrap_or_else(|_| Err(Error::JobPanicked { kind: kind.clone() }));
+
+    match res {
+        Ok(()) => JobRepository::complete(&root_ctx, db_context, &job).await?,
+        Err(ex) => {
+            let retry_in_sec = (attempts < max_attempts)
+                .then(|| backoff_delay(settings.retry_base, settings.retry_max, attempts).as_secs_f64());
+            if retry_in_sec.is_none() {
+                warn!("{:<12} - {kind} ({id}) dead after {attempts} attempt(s): {ex}", "JOB");
+            }
+
+            JobRepository::fail(&root_ctx, db_context, &job, ex.to_string(), retry_in_sec).await?;
+        }
+    }
+
+    Ok(true)
+}
+
+// endregion: -- Worker Pool
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use anyhow::Result;
+    use serde::Deserialize;
+    use serial_test::serial;
+
+    #[derive(Serialize, Deserialize)]
+    struct FailingJob {
+        message: String,
+    }
+
+    #[async_trait]
+    impl JobPayload for FailingJob {
+        const KIND: &'static str = "test_failing_job";
+        const MAX_ATTEMPTS: i32 = 2;
+
+        async fn run(self, _ctx: &Ctx, _db_context: &DbContext) -> super::Result<()> {
+            Err(Error::JobFail(self.message))
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_run_next_retry_then_dead() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let registry = JobRegistry::new().register::<FailingJob>();
+        let settings = WorkerSettings { retry_base: Duration::ZERO, ..Default::default() };
+        let id = enqueue(&ctx, &db_context, &FailingJob { message: "boom".to_string() }).await?;
+
+        // -- Exec
+        let first = run_next(&db_context, &registry, &settings).await?;
+        let job_after_first = JobRepository::get(&ctx, &db_context, id).await?;
+        let second = run_next(&db_context, &registry, &settings).await?;
+        let third = run_next(&db_context, &registry, &settings).await?;
+
+        // -- Check
+        assert!(first && second && !third);
+        assert_eq!(job_after_first.status, "queued");
+        let job = JobRepository::get(&ctx, &db_context, id).await?;
+        assert_eq!(job.status, "dead");
+        assert_eq!(job.attempts, 2);
+        assert!(job.last_error.is_some_and(|e| e.contains("boom")));
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
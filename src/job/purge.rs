@@ -0,0 +1,98 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use crate::ctx::Ctx;
+use crate::job::{enqueue_at, JobPayload, Result};
+use crate::model::job::JobRepository;
+use crate::model::webhook::WebhookDeliveryRepository;
+use crate::model::DbContext;
+use crate::utils::time_utils::now_utc;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(24 * 3600);
+
+/// Deletes the done jobs and the finished webhook deliveries older than
+/// `keep_days`, then schedules the next run, a day later.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PurgeHistory {
+    pub keep_days: i32,
+}
+
+#[async_trait]
+impl JobPayload for PurgeHistory {
+    const KIND: &'static str = "purge_history";
+
+    async fn run(self, ctx: &Ctx, db_context: &DbContext) -> Result<()> {
+        let jobs = JobRepository::purge_done(ctx, db_context, self.keep_days).await?;
+        let deliveries = WebhookDeliveryRepository::purge_finished(ctx, db_context, self.keep_days).await?;
+        info!("{:<12} - purged {jobs} job(s), {deliveries} webhook delivery(ies)", "JOB");
+
+        schedule(ctx, db_context, self.keep_days, PURGE_INTERVAL).await
+    }
+}
+
+/// Unless one is already queued, e.g. by another instance or a retried run.
+pub async fn schedule(ctx: &Ctx, db_context: &DbContext, keep_days: i32, delay: Duration) -> Result<()> {
+    if JobRepository::has_queued(ctx, db_context, PurgeHistory::KIND).await? {
+        return Ok(());
+    }
+
+    enqueue_at(ctx, db_context, &PurgeHistory { keep_days }, now_utc() + delay).await?;
+
+    Ok(())
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use crate::job::{enqueue, run_next, JobRegistry, WorkerSettings};
+    use crate::model::job::{JobStatus, JobRepository};
+    use crate::model::Error;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[derive(Serialize, Deserialize)]
+    struct NoopJob;
+
+    #[async_trait]
+    impl JobPayload for NoopJob {
+        const KIND: &'static str = "test_noop_job";
+
+        async fn run(self, _ctx: &Ctx, _db_context: &DbContext) -> super::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_purge_history_ok_rescheduled() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let registry = JobRegistry::new().register::<NoopJob>().register::<PurgeHistory>();
+        let settings = WorkerSettings::default();
+        let noop_id = enqueue(&ctx, &db_context, &NoopJob).await?;
+        run_next(&db_context, &registry, &settings).await?;
+        // Keeping no day, the done job is already old enough.
+        schedule(&ctx, &db_context, 0, Duration::ZERO).await?;
+        // A second schedule is a no-op.
+        schedule(&ctx, &db_context, 0, Duration::ZERO).await?;
+
+        // -- Exec
+        let ran = run_next(&db_context, &registry, &settings).await?;
+
+        // -- Check
+        assert!(ran);
+        let res = JobRepository::get(&ctx, &db_context, noop_id).await;
+        assert!(matches!(res, Err(Error::EntityNotFound { .. })), "Should be purged, was `{res:?}`");
+        let queued = JobRepository::list(&ctx, &db_context, Some(JobStatus::Queued)).await?;
+        let next_purges = queued.iter().filter(|job| job.kind == PurgeHistory::KIND).count();
+        assert_eq!(next_purges, 1, "Should have scheduled the next run once");
+        assert!(!run_next(&db_context, &registry, &settings).await?, "Should run tomorrow");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,25 @@
+use std::fmt::Formatter;
+use derive_more::From;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+    UnknownSinkBackend(String),
+    /// The file writer is behind, the line is dropped.
+    SinkFull,
+    SinkClosed,
+
+    #[from]
+    SerdeJson(serde_json::Error),
+    #[from]
+    Io(std::io::Error),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
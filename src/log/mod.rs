@@ -1,72 +1,257 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+mod error;
+mod redact;
+mod sink;
+
+pub use self::error::{Error, Result};
+pub use self::redact::{redactor, Redactor, REDACTED};
+pub use self::sink::{MemorySink, RequestLogSink, RotatingFileSink, StdoutSink};
+
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
 use axum::http::{Method, Uri};
-use log::info;
-use serde_json::{json, Value};
-use serde_with::serde_derive::Serialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use serde_with::skip_serializing_none;
-use tracing::log::debug;
-use uuid::Uuid;
+use tracing::warn;
+use crate::config::Config;
 use crate::ctx::Ctx;
-use crate::Result;
+use crate::utils::time_utils::{format_time, now_utc};
 use crate::web::{self, ClientError};
 use crate::web::rpc::RpcInfo;
 
-#[skip_serializing_none]
-#[derive(Serialize)]
-struct RequestLogLine {
-    uuid: String,
-    timestamp: String, // ISO8601
+// region: -- Sink
 
-    user_id: Option<i64>,
+static SINK: OnceLock<Box<dyn RequestLogSink>> = OnceLock::new();
 
-    req_path: String,
-    req_method: String,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestLogBackend {
+    Stdout,
+    /// JSON Lines files in `SERVICE_REQUEST_LOG_DIR`, rotated by size.
+    File,
+}
 
-    rpc_id: Option<String>,
-    rpc_method: Option<String>,
+impl FromStr for RequestLogBackend {
+    type Err = Error;
 
-    client_error_type: Option<String>,
-    error_type: Option<String>,
-    error_data: Option<Value>,
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "stdout" => Ok(RequestLogBackend::Stdout),
+            "file" => Ok(RequestLogBackend::File),
+            other => Err(Error::UnknownSinkBackend(other.to_string())),
+        }
+    }
 }
 
-pub async fn log_request(
-    uuid: Uuid,
-    method: Method,
-    uri: Uri,
-    rpc_info: Option<&RpcInfo>,
-    ctx: Option<Ctx>,
-    service_error: Option<&web::Error>,
-    client_error: Option<ClientError>,
-) -> Result<()> {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-
-    let error_type = service_error.map(|se| se.as_ref().to_string());
-    let error_data = serde_json::to_value(service_error)
-        .ok().
-        and_then(|mut v| v.get_mut("data").map(|v| v.take()));
-
-    let log_line = RequestLogLine {
-        uuid: uuid.to_string(),
-        timestamp: timestamp.to_string(),
-
-        req_path: uri.to_string(),
-        req_method: method.to_string(),
-
-        user_id: ctx.map(|c| c.user_id()),
-
-        rpc_id: rpc_info.and_then(|rpc| rpc.id.as_ref().map(|id| id.to_string())),
-        rpc_method: rpc_info.map(|rpc| rpc.method.to_string()),
-
-        client_error_type: client_error.map(|e| e.as_ref().to_string()),
-        error_type,
-        error_data,
+/// Sets the sink of `log_request`, once. Until then, the lines go to stdout.
+pub fn init_sink(config: &Config) -> Result<()> {
+    let sink: Box<dyn RequestLogSink> = match config.REQUEST_LOG {
+        RequestLogBackend::Stdout => Box::new(StdoutSink),
+        RequestLogBackend::File => Box::new(RotatingFileSink::new(
+            &config.REQUEST_LOG_DIR,
+            config.REQUEST_LOG_MAX_BYTES,
+            config.REQUEST_LOG_MAX_FILES,
+        )?),
     };
-
-    info!("LOG_REQUEST: \n{}", json!(log_line));
+    let _ = SINK.set(sink);
 
     Ok(())
 }
+
+/// The lines still queued are written, before exiting.
+pub fn flush_sink() {
+    if let Some(Err(ex)) = SINK.get().map(|sink| sink.flush()) {
+        warn!("{:<12} - fail to flush the request log - {ex}", "LOG_REQUEST");
+    }
+}
+
+fn sink() -> &'static dyn RequestLogSink {
+    SINK.get_or_init(|| Box::new(StdoutSink)).as_ref()
+}
+
+// endregion: -- Sink
+
+#[skip_serializing_none]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestLogLine {
+    pub req_id: String,
+    /// RFC 3339, when the request completed.
+    pub timestamp: String,
+    pub duration_ms: f64,
+    /// None for the calls over the websocket.
+    pub status: Option<u16>,
+    pub client_ip: Option<IpAddr>,
+
+    pub user_id: Option<i64>,
+
+    pub req_path: String,
+    pub req_method: String,
+
+    pub rpc_id: Option<String>,
+    pub rpc_method: Option<String>,
+    pub rpc_params: Option<Value>,
+
+    pub client_error_type: Option<String>,
+    pub error_type: Option<String>,
+    pub error_data: Option<Value>,
+}
+
+pub struct RequestLog<'a> {
+    pub req_id: &'a str,
+    pub method: &'a Method,
+    pub uri: &'a Uri,
+    pub status: Option<u16>,
+    pub duration: Duration,
+    pub client_ip: Option<IpAddr>,
+    pub rpc_info: Option<&'a RpcInfo>,
+    pub ctx: Option<&'a Ctx>,
+    pub service_error: Option<&'a web::Error>,
+    pub client_error: Option<&'a ClientError>,
+}
+
+pub fn log_request(log: RequestLog) {
+    log_to(sink(), log);
+}
+
+/// A failing sink is reported, the request is not failed for it.
+fn log_to(sink: &dyn RequestLogSink, log: RequestLog) {
+    let line = RequestLogLine::from(log);
+
+    if let Err(ex) = sink.write(&line) {
+        warn!("{:<12} - fail to write the request log - {ex}", "LOG_REQUEST");
+    }
+}
+
+/// The params and the error data are redacted, the line is then safe to hand to a sink.
+impl From<RequestLog<'_>> for RequestLogLine {
+    fn from(log: RequestLog) -> Self {
+        let error_type = log.service_error.map(|se| se.as_ref().to_string());
+        let error_data = serde_json::to_value(log.service_error)
+            .ok()
+            .and_then(|mut v| v.get_mut("data").map(|v| v.take()))
+            .map(|v| redactor().redact(v));
+
+        RequestLogLine {
+            req_id: log.req_id.to_string(),
+            timestamp: format_time(now_utc()),
+            duration_ms: log.duration.as_secs_f64() * 1000.,
+            status: log.status,
+            client_ip: log.client_ip,
+
+            user_id: log.ctx.map(|c| c.user_id()),
+
+            req_path: redactor().redact_uri(log.uri),
+            req_method: log.method.to_string(),
+
+            rpc_id: log.rpc_info.and_then(|rpc| rpc.id.as_ref().map(|id| id.to_string())),
+            rpc_method: log.rpc_info.map(|rpc| rpc.method.to_string()),
+            rpc_params: log.rpc_info.and_then(|rpc| rpc.params.clone()).map(|v| redactor().redact(v)),
+
+            client_error_type: log.client_error.map(|e| e.as_ref().to_string()),
+            error_type,
+            error_data,
+        }
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use crate::utils::time_utils::parse_utc;
+    use serde_json::json;
+
+    #[test]
+    fn test_request_log_line_fields() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_uri: Uri = "/api/rpc".parse()?;
+        let fx_ctx = Ctx::new(1000)?;
+        let fx_rpc_info = RpcInfo {
+            id: Some(Value::from(7)),
+            method: "get_task".to_string(),
+            params: Some(json!({ "id": 7 })),
+        };
+        let fx_service_error = web::Error::RpcMethodUnknown("get_task".to_string());
+        let (_, fx_client_error) = fx_service_error.client_status_and_error();
+        let sink = MemorySink::default();
+
+        // -- Exec
+        let line = RequestLogLine::from(RequestLog {
+            req_id: "fx-req-id",
+            method: &Method::POST,
+            uri: &fx_uri,
+            status: Some(400),
+            duration: Duration::from_micros(2500),
+            client_ip: Some("127.0.0.1".parse()?),
+            rpc_info: Some(&fx_rpc_info),
+            ctx: Some(&fx_ctx),
+            service_error: Some(&fx_service_error),
+            client_error: Some(&fx_client_error),
+        });
+        sink.write(&line)?;
+
+        // -- Check
+        let lines = sink.lines();
+        assert_eq!(lines.len(), 1);
+        let line = &lines[0];
+        parse_utc(&line.timestamp)?;
+        assert_eq!(line.req_id, "fx-req-id");
+        assert_eq!(line.duration_ms, 2.5);
+        assert_eq!(line.status, Some(400));
+        assert_eq!(line.client_ip, Some("127.0.0.1".parse()?));
+        assert_eq!(line.user_id, Some(1000));
+        assert_eq!(line.rpc_id.as_deref(), Some("7"));
+        assert_eq!(line.rpc_params, Some(json!({ "id": 7 })));
+        assert_eq!(line.error_type.as_deref(), Some("RpcMethodUnknown"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_log_line_secrets_redacted() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_secret = "fx-secret-b7f1";
+        let fx_uri: Uri = format!("/api/rpc?auth-token={fx_secret}").parse()?;
+        let fx_rpc_info = RpcInfo {
+            id: None,
+            method: "create_user".to_string(),
+            params: Some(json!({
+                "data": {
+                    "username": "demo1",
+                    "pwd_clear": fx_secret,
+                    "password": fx_secret,
+                    "webhooks": [{ "url": "https://example.com", "secret": fx_secret }],
+                },
+                "auth-token": fx_secret,
+                "X-Api-Key": fx_secret,
+            })),
+        };
+        let fx_service_error = web::Error::RpcFailJsonParams { rpc_method: "create_user".to_string() };
+        let sink = MemorySink::default();
+
+        // -- Exec
+        log_to(&sink, RequestLog {
+            req_id: "fx-req-id",
+            method: &Method::POST,
+            uri: &fx_uri,
+            status: Some(400),
+            duration: Duration::ZERO,
+            client_ip: None,
+            rpc_info: Some(&fx_rpc_info),
+            ctx: None,
+            service_error: Some(&fx_service_error),
+            client_error: None,
+        });
+
+        // -- Check
+        let written = serde_json::to_string(&sink.lines())?;
+        assert!(!written.contains(fx_secret), "Should not reach the sink: {written}");
+        assert!(written.contains("demo1"), "Should keep the other params: {written}");
+        assert!(written.contains(REDACTED));
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,200 @@
+use std::sync::OnceLock;
+use axum::http::{HeaderMap, Uri};
+use serde::Serialize;
+use serde_json::Value;
+use crate::config::config;
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Always redacted, `SERVICE_LOG_REDACT_KEYS` can only add to them.
+const DEFAULT_KEYS: &[&str] = &[
+    "password",
+    "pwd",
+    "token",
+    "auth-token",
+    "api-key",
+    "apikey",
+    "secret",
+    "authorization",
+    "cookie",
+];
+
+pub fn redactor() -> &'static Redactor {
+    static INSTANCE: OnceLock<Redactor> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| Redactor::new(&config().LOG_REDACT_KEYS))
+}
+
+/// Hides the values of the fields (or headers) whose name contains one of
+/// the denylisted keys, ignoring the case and `-` vs `_`, e.g. `token`
+/// matches `auth-token`, `token_salt` and `X-Refresh-Token`.
+pub struct Redactor {
+    keys: Vec<String>,
+}
+
+impl Redactor {
+    pub fn new(extra_keys: &[String]) -> Self {
+        let keys = DEFAULT_KEYS
+            .iter()
+            .copied()
+            .chain(extra_keys.iter().map(String::as_str))
+            .map(normalize)
+            .filter(|key| !key.is_empty())
+            .collect();
+
+        Self { keys }
+    }
+
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        let name = normalize(name);
+
+        self.keys.iter().any(|key| name.contains(key.as_str()))
+    }
+
+    /// Redacts the matching object members, at any depth.
+    pub fn redact(&self, mut value: Value) -> Value {
+        self.redact_in_place(&mut value);
+
+        value
+    }
+
+    /// For the debug output of a value, as redacted JSON.
+    pub fn redact_json(&self, value: &impl Serialize) -> Value {
+        serde_json::to_value(value)
+            .map(|value| self.redact(value))
+            .unwrap_or_else(|_| Value::from(REDACTED))
+    }
+
+    /// For the debug output of the headers, e.g. the `set-cookie` of the login.
+    pub fn redact_headers(&self, headers: &HeaderMap) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                let value = if self.is_sensitive(name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    String::from_utf8_lossy(value.as_bytes()).to_string()
+                };
+                (name.to_string(), value)
+            })
+            .collect()
+    }
+
+    /// The path and query of a request, e.g. `/events?token=[REDACTED]`.
+    pub fn redact_uri(&self, uri: &Uri) -> String {
+        let Some(query) = uri.query() else {
+            return uri.path().to_string();
+        };
+
+        let pairs: Vec<String> = query
+            .split('&')
+            .map(|pair| match pair.split_once('=') {
+                Some((name, _)) if self.is_sensitive(name) => format!("{name}={REDACTED}"),
+                _ => pair.to_string(),
+            })
+            .collect();
+
+        format!("{}?{}", uri.path(), pairs.join("&"))
+    }
+
+    fn redact_in_place(&self, value: &mut Value) {
+        match value {
+            Value::Object(members) => {
+                for (name, member) in members.iter_mut() {
+                    if self.is_sensitive(name) {
+                        *member = Value::from(REDACTED);
+                    } else {
+                        self.redact_in_place(member);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_in_place(item)),
+            _ => {}
+        }
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase().replace('_', "-")
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use axum::http::header::{CONTENT_TYPE, SET_COOKIE};
+    use serde_json::json;
+
+    #[test]
+    fn test_redact_nested_and_extra_keys() -> Result<()> {
+        // -- Setup & Fixtures
+        let redactor = Redactor::new(&["Card_Number".to_string()]);
+        let fx_value = json!({
+            "username": "demo1",
+            "pwd_clear": "fx-secret-1",
+            "data": {
+                "Auth-Token": "fx-secret-2",
+                "items": [{ "api_key": "fx-secret-3", "title": "kept" }],
+                "card-number": "fx-secret-4",
+            },
+        });
+
+        // -- Exec
+        let value = redactor.redact(fx_value);
+
+        // -- Check
+        assert_eq!(
+            value,
+            json!({
+                "username": "demo1",
+                "pwd_clear": REDACTED,
+                "data": {
+                    "Auth-Token": REDACTED,
+                    "items": [{ "api_key": REDACTED, "title": "kept" }],
+                    "card-number": REDACTED,
+                },
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_redact_headers() -> Result<()> {
+        // -- Setup & Fixtures
+        let redactor = Redactor::new(&[]);
+        let mut fx_headers = HeaderMap::new();
+        fx_headers.insert(SET_COOKIE, "auth-token=fx-secret; Path=/".parse()?);
+        fx_headers.insert(CONTENT_TYPE, "application/json".parse()?);
+
+        // -- Exec
+        let headers = redactor.redact_headers(&fx_headers);
+
+        // -- Check
+        assert_eq!(
+            headers,
+            [
+                ("set-cookie".to_string(), REDACTED.to_string()),
+                ("content-type".to_string(), "application/json".to_string()),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_redact_uri() -> Result<()> {
+        // -- Setup & Fixtures
+        let redactor = Redactor::new(&[]);
+        let fx_uri: Uri = "http://localhost:8080/api/tasks?page=2&api_key=fx-secret&flag".parse()?;
+        let fx_path_only: Uri = "/api/tasks".parse()?;
+
+        // -- Exec & Check
+        assert_eq!(redactor.redact_uri(&fx_uri), "/api/tasks?page=2&api_key=[REDACTED]&flag");
+        assert_eq!(redactor.redact_uri(&fx_path_only), "/api/tasks");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
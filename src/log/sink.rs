@@ -0,0 +1,253 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::Mutex;
+use std::thread;
+use tracing::warn;
+use crate::log::{Error, RequestLogLine, Result};
+
+/// Where the request log lines go, one JSON object per line.
+pub trait RequestLogSink: Send + Sync {
+    fn write(&self, line: &RequestLogLine) -> Result<()>;
+
+    /// Returns once the lines written before are out.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// region: -- StdoutSink
+
+pub struct StdoutSink;
+
+impl RequestLogSink for StdoutSink {
+    fn write(&self, line: &RequestLogLine) -> Result<()> {
+        let json = serde_json::to_string(line)?;
+        writeln!(io::stdout().lock(), "{json}")?;
+
+        Ok(())
+    }
+}
+
+// endregion: -- StdoutSink
+
+// region: -- RotatingFileSink
+
+/// Appends to `{dir}/requests.jsonl`. Once over `max_bytes`, it is renamed
+/// `requests.1.jsonl` (the older ones shifted to `.2`, `.3`...), keeping
+/// at most `max_files` rotated files.
+///
+/// The file I/O runs on a writer thread, `write` only queues the line and
+/// never blocks the async path. A line is dropped when the queue is full.
+pub struct RotatingFileSink {
+    tx: SyncSender<WriterCommand>,
+}
+
+enum WriterCommand {
+    Line(String),
+    Flush(SyncSender<()>),
+}
+
+struct FileWriter {
+    dir: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    current: CurrentFile,
+}
+
+struct CurrentFile {
+    file: File,
+    size: u64,
+}
+
+const FILE_STEM: &str = "requests";
+/// Lines queued for the writer thread.
+const WRITER_CAPACITY: usize = 8192;
+
+impl RotatingFileSink {
+    /// The writer thread stops once the sink is dropped.
+    pub fn new(dir: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let current = open_current(&dir)?;
+        let writer = FileWriter { dir, max_bytes, max_files, current };
+
+        let (tx, rx) = mpsc::sync_channel(WRITER_CAPACITY);
+        thread::Builder::new()
+            .name("request-log".to_string())
+            .spawn(move || writer.run(rx))?;
+
+        Ok(Self { tx })
+    }
+}
+
+impl FileWriter {
+    fn run(mut self, rx: Receiver<WriterCommand>) {
+        for command in rx {
+            match command {
+                WriterCommand::Line(json) => {
+                    if let Err(ex) = self.write(&json) {
+                        warn!("{:<12} - fail to write the request log - {ex}", "LOG_REQUEST");
+                    }
+                }
+                WriterCommand::Flush(done) => {
+                    let _ = self.current.file.flush();
+                    let _ = done.send(());
+                }
+            }
+        }
+    }
+
+    fn write(&mut self, json: &str) -> Result<()> {
+        if self.current.size > 0 && self.current.size + json.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.current.file.write_all(json.as_bytes())?;
+        self.current.size += json.len() as u64;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        let _ = fs::remove_file(rotated_path(&self.dir, self.max_files));
+        for index in (1..self.max_files).rev() {
+            let from = rotated_path(&self.dir, index);
+            if from.exists() {
+                fs::rename(from, rotated_path(&self.dir, index + 1))?;
+            }
+        }
+        if self.max_files > 0 {
+            fs::rename(current_path(&self.dir), rotated_path(&self.dir, 1))?;
+        } else {
+            fs::remove_file(current_path(&self.dir))?;
+        }
+
+        self.current = open_current(&self.dir)?;
+
+        Ok(())
+    }
+}
+
+impl RequestLogSink for RotatingFileSink {
+    fn write(&self, line: &RequestLogLine) -> Result<()> {
+        let mut json = serde_json::to_string(line)?;
+        json.push('\n');
+
+        self.tx.try_send(WriterCommand::Line(json)).map_err(|ex| match ex {
+            TrySendError::Full(_) => Error::SinkFull,
+            TrySendError::Disconnected(_) => Error::SinkClosed,
+        })
+    }
+
+    /// Blocking, for the shutdown and the tests.
+    fn flush(&self) -> Result<()> {
+        let (done_tx, done_rx) = mpsc::sync_channel(1);
+        self.tx.send(WriterCommand::Flush(done_tx)).map_err(|_| Error::SinkClosed)?;
+
+        done_rx.recv().map_err(|_| Error::SinkClosed)
+    }
+}
+
+fn current_path(dir: &Path) -> PathBuf {
+    dir.join(format!("{FILE_STEM}.jsonl"))
+}
+
+fn rotated_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("{FILE_STEM}.{index}.jsonl"))
+}
+
+fn open_current(dir: &Path) -> Result<CurrentFile> {
+    let file = OpenOptions::new().create(true).append(true).open(current_path(dir))?;
+    let size = file.metadata()?.len();
+
+    Ok(CurrentFile { file, size })
+}
+
+// endregion: -- RotatingFileSink
+
+// region: -- MemorySink
+
+/// Keeps the lines, for the tests.
+#[derive(Default)]
+pub struct MemorySink {
+    lines: Mutex<Vec<RequestLogLine>>,
+}
+
+impl MemorySink {
+    pub fn lines(&self) -> Vec<RequestLogLine> {
+        self.lines.lock().unwrap_or_else(|ex| ex.into_inner()).clone()
+    }
+}
+
+impl RequestLogSink for MemorySink {
+    fn write(&self, line: &RequestLogLine) -> Result<()> {
+        self.lines.lock().unwrap_or_else(|ex| ex.into_inner()).push(line.clone());
+
+        Ok(())
+    }
+}
+
+// endregion: -- MemorySink
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn fx_line(req_id: &str) -> RequestLogLine {
+        RequestLogLine {
+            req_id: req_id.to_string(),
+            timestamp: "2026-01-01T00:00:00Z".to_string(),
+            duration_ms: 1.5,
+            status: Some(200),
+            client_ip: None,
+            user_id: None,
+            req_path: "/api/rpc".to_string(),
+            req_method: "POST".to_string(),
+            rpc_id: None,
+            rpc_method: None,
+            rpc_params: None,
+            client_error_type: None,
+            error_type: None,
+            error_data: None,
+        }
+    }
+
+    #[test]
+    fn test_rotating_file_sink_rotate_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_dir = std::env::temp_dir().join("webapi_test_rotating_file_sink");
+        let _ = fs::remove_dir_all(&fx_dir);
+        let fx_line_len = serde_json::to_string(&fx_line("req-0"))?.len() as u64 + 1;
+        // Two lines per file, at most two rotated files.
+        let sink = RotatingFileSink::new(&fx_dir, fx_line_len * 2, 2)?;
+
+        // -- Exec
+        for index in 0..7 {
+            sink.write(&fx_line(&format!("req-{index}")))?;
+        }
+        sink.flush()?;
+
+        // -- Check
+        let read_ids = |path: PathBuf| -> Result<Vec<String>> {
+            let content = fs::read_to_string(path)?;
+            let lines = content
+                .lines()
+                .map(|line| serde_json::from_str::<RequestLogLine>(line).map(|line| line.req_id))
+                .collect::<core::result::Result<_, _>>()?;
+            Ok(lines)
+        };
+        assert_eq!(read_ids(current_path(&fx_dir))?, ["req-6"]);
+        assert_eq!(read_ids(rotated_path(&fx_dir, 1))?, ["req-4", "req-5"]);
+        assert_eq!(read_ids(rotated_path(&fx_dir, 2))?, ["req-2", "req-3"]);
+        assert!(!rotated_path(&fx_dir, 3).exists(), "Should have dropped the oldest file");
+
+        // -- Clean
+        fs::remove_dir_all(&fx_dir)?;
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
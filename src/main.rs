@@ -2,24 +2,33 @@
 
 mod pwd;
 mod token;
+mod cli;
 mod ctx;
 mod error;
+mod event;
+mod job;
 mod log;
+mod metrics;
 mod model;
+mod rate_limit;
+mod telemetry;
 mod web;
 mod utils;
+mod webhook;
 
 pub mod _dev_utils;
 pub mod config;
 
 pub use self::error::{Error, Result};
-use model::ticket::TicketRepository;
 
+use crate::cli::{Cli, Command, ServeArgs};
+use crate::config::RunMode;
 use crate::ctx::Ctx;
-use crate::log::log_request;
+use crate::model::migration;
 use crate::model::DbContext;
 use crate::web::routes_static::serve_dir;
-use axum::extract::{Path, Query};
+use axum::extract::{DefaultBodyLimit, Path, Query};
+use clap::Parser;
 use axum::http::{Method, Uri};
 use axum::response::Response;
 use axum::routing::{get, get_service, Route};
@@ -31,49 +40,155 @@ use axum::{
 use serde::Deserialize;
 use serde_json::json;
 use std::net::SocketAddr;
+use std::future::IntoFuture;
+use std::time::Duration;
 use tower_cookies::CookieManagerLayer;
 use tower_http::services::ServeDir;
 use tracing::log::{debug, info};
-use tracing_subscriber::EnvFilter;
 use uuid::Uuid;
 use crate::web::middlewares::response_mapper;
 use crate::web::middlewares::auth::{mw_ctx_resolver, mw_require_auth};
 use crate::web::middlewares::response_mapper::mw_response_mapper;
+use crate::web::middlewares::body_limit::mw_body_limit;
+use crate::web::middlewares::security::{cors_layer, mw_security_headers};
+use crate::web::middlewares::stamp::mw_req_stamp;
 use crate::web::rpc;
 
+/// How long the open connections are waited for, once stopping.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(10);
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .without_time()
-        .with_target(false)
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
+    let command = Cli::parse().command.unwrap_or(Command::Serve(ServeArgs::default()));
+
+    // Generating the keys is done before having a config.
+    let telemetry = if matches!(command, Command::Keys(_)) {
+        telemetry::init(None, "webapi")?
+    } else {
+        let config = config::init()?;
+        telemetry::init(config.OTEL_ENDPOINT.as_deref(), &config.OTEL_SERVICE_NAME)?
+    };
+
+    let res = match command {
+        Command::Serve(args) => serve(args).await,
+        Command::Migrate(command) => Ok(cli::exec_migrate(command).await?),
+        Command::User(command) => Ok(cli::exec_user(command).await?),
+        Command::Keys(command) => Ok(cli::exec_keys(command)?),
+        Command::Token(command) => Ok(cli::exec_token(command).await?),
+    };
+    log::flush_sink();
+    telemetry.shutdown();
+
+    res
+}
 
+async fn serve(args: ServeArgs) -> Result<()> {
     // -- FOR DEV ONLY
-    _dev_utils::init_dev().await;
+    if config::config().RUN_MODE == RunMode::Dev && config::config().DEV_RESET_DB {
+        _dev_utils::init_dev().await;
+    }
+
+    log::init_sink(config::config())?;
 
     // Initialize managers
     let db = DbContext::new().await?;
 
-    let routes_rpc = rpc::routes(db.clone())
+    if config::config().DB_MIGRATE_ON_START {
+        migration::migrate_up(&db).await?;
+    }
+
+    rate_limit::init(config::config(), &db);
+
+    let webhook_settings = webhook::DeliverySettings {
+        allow_private_targets: config::config().WEBHOOK_ALLOW_PRIVATE_TARGETS,
+        ..Default::default()
+    };
+    let mut workers = webhook::start(db.clone(), webhook_settings);
+
+    let job_registry = job::JobRegistry::new().register::<job::purge::PurgeHistory>();
+    job::purge::schedule(&Ctx::root_ctx(), &db, config::config().JOB_HISTORY_KEEP_DAYS, Duration::ZERO).await?;
+    let job_settings = job::WorkerSettings {
+        concurrency: config::config().JOB_WORKERS,
+        ..Default::default()
+    };
+    workers.extend(job::start(db.clone(), job_registry, job_settings));
+
+    let routes_api = rpc::routes(db.clone())
+        .merge(web::routes_tasks::routes(db.clone()))
+        .merge(web::routes_tickets::routes(db.clone()))
+        .merge(web::routes_events::routes(db.clone()))
         .route_layer(middleware::from_fn(mw_require_auth));
 
     // register routes
     let routes_all = Router::new()
         .merge(web::routes_login::routes(db.clone()))
-        .nest("/api", routes_rpc)
+        .nest("/api", routes_api)
+        .layer(middleware::from_fn(mw_body_limit))
+        .layer(DefaultBodyLimit::disable())
         .layer(middleware::map_response(mw_response_mapper))
         .layer(middleware::from_fn_with_state(db.clone(), mw_ctx_resolver))
         .layer(CookieManagerLayer::new())
         .fallback_service(serve_dir());
 
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
+    // Merged after the layers, so the probes bypass them. The request id,
+    // security headers and CORS are still on every response.
+    let routes_all = Router::new()
+        .merge(web::routes_health::routes(db.clone(), workers))
+        .merge(web::routes_metrics::routes(db.clone()))
+        .merge(routes_all)
+        .layer(middleware::from_fn(mw_req_stamp))
+        .layer(middleware::from_fn(mw_security_headers));
+    let routes_all = match cors_layer(config::config()) {
+        Some(cors) => routes_all.layer(cors),
+        None => routes_all,
+    };
+
+    let addr = args.addr.unwrap_or(config::config().BIND_ADDR);
     info!("{:<12} - {addr}\n", "LISTENING");
 
     let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    axum::serve(listener, routes_all.into_make_service())
-        .await
-        .unwrap();
+    // Returns on SIGTERM or Ctrl-C, so `main` flushes the request log and the spans.
+    let (stopping_tx, stopping_rx) = tokio::sync::oneshot::channel();
+    let server = axum::serve(listener, routes_all.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            let _ = stopping_tx.send(());
+        });
+
+    // The websockets and event streams stay open, they are only given a grace period.
+    tokio::select! {
+        res = server.into_future() => res.unwrap(),
+        _ = async {
+            if stopping_rx.await.is_ok() {
+                tokio::time::sleep(SHUTDOWN_GRACE).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        } => info!("{:<12} - connections still open after {SHUTDOWN_GRACE:?}, closed", "SHUTDOWN"),
+    }
 
     Ok(())
 }
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    info!("{:<12} - stopping, waiting for the requests in progress", "SHUTDOWN");
+}
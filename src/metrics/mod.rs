@@ -0,0 +1,202 @@
+use std::future::Future;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+/// Label for the requests matching no route, e.g. the static files.
+pub const ROUTE_FALLBACK: &str = "fallback";
+
+pub fn metrics() -> &'static Metrics {
+    static INSTANCE: OnceLock<Metrics> = OnceLock::new();
+
+    INSTANCE.get_or_init(Metrics::new)
+}
+
+/// Labels are bounded: route templates, RPC method names and `ClientError`
+/// types, never raw paths or ids.
+pub struct Metrics {
+    registry: Registry,
+
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    rpc_requests: IntCounterVec,
+    rpc_request_duration: HistogramVec,
+
+    pub db_pool_size: IntGauge,
+    pub db_pool_idle: IntGauge,
+    pub db_pool_waiters: IntGauge,
+    db_query_duration: HistogramVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("webapi".to_string()), None)
+            .expect("valid metrics prefix");
+
+        let metrics = Self {
+            http_requests: IntCounterVec::new(
+                Opts::new("http_requests_total", "HTTP requests"),
+                &["method", "route", "status", "client_error"],
+            ).expect("valid metric"),
+            http_request_duration: HistogramVec::new(
+                HistogramOpts::new("http_request_duration_seconds", "HTTP request duration"),
+                &["method", "route"],
+            ).expect("valid metric"),
+            rpc_requests: IntCounterVec::new(
+                Opts::new("rpc_requests_total", "JSON-RPC calls"),
+                &["rpc_method", "client_error"],
+            ).expect("valid metric"),
+            rpc_request_duration: HistogramVec::new(
+                HistogramOpts::new("rpc_request_duration_seconds", "JSON-RPC call duration"),
+                &["rpc_method"],
+            ).expect("valid metric"),
+
+            db_pool_size: IntGauge::new("db_pool_size", "Open database connections")
+                .expect("valid metric"),
+            db_pool_idle: IntGauge::new("db_pool_idle", "Idle database connections")
+                .expect("valid metric"),
+            db_pool_waiters: IntGauge::new(
+                "db_pool_waiters",
+                "Model functions waiting for a database connection",
+            ).expect("valid metric"),
+            db_query_duration: HistogramVec::new(
+                HistogramOpts::new("db_query_duration_seconds", "Database query duration")
+                    .buckets(vec![0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1., 2.5]),
+                &["op", "table"],
+            ).expect("valid metric"),
+
+            registry,
+        };
+
+        for collector in [
+            Box::new(metrics.http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(metrics.http_request_duration.clone()),
+            Box::new(metrics.rpc_requests.clone()),
+            Box::new(metrics.rpc_request_duration.clone()),
+            Box::new(metrics.db_pool_size.clone()),
+            Box::new(metrics.db_pool_idle.clone()),
+            Box::new(metrics.db_pool_waiters.clone()),
+            Box::new(metrics.db_query_duration.clone()),
+        ] {
+            metrics.registry.register(collector).expect("metric registered once");
+        }
+
+        metrics
+    }
+
+    /// Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        TextEncoder::new()
+            .encode(&self.registry.gather(), &mut buffer)
+            .expect("text encoding of valid metrics");
+
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+
+    pub fn record_request(&self, request: &RequestMetrics) {
+        let client_error = request.client_error.unwrap_or("");
+
+        self.http_requests
+            .with_label_values(&[request.method, request.route, &request.status.to_string(), client_error])
+            .inc();
+        self.http_request_duration
+            .with_label_values(&[request.method, request.route])
+            .observe(request.duration.as_secs_f64());
+
+        if let Some(rpc_method) = request.rpc_method {
+            self.record_rpc_call(rpc_method, request.client_error, request.duration);
+        }
+    }
+
+    /// Alone for the calls over the websocket, which are not requests of their own.
+    pub fn record_rpc_call(&self, rpc_method: &str, client_error: Option<&str>, duration: Duration) {
+        self.rpc_requests
+            .with_label_values(&[rpc_method, client_error.unwrap_or("")])
+            .inc();
+        self.rpc_request_duration
+            .with_label_values(&[rpc_method])
+            .observe(duration.as_secs_f64());
+    }
+
+    pub async fn time_query<F: Future>(&self, op: &str, table: &str, query: F) -> F::Output {
+        let start = Instant::now();
+        let res = query.await;
+        self.db_query_duration
+            .with_label_values(&[op, table])
+            .observe(start.elapsed().as_secs_f64());
+
+        res
+    }
+}
+
+pub struct RequestMetrics<'a> {
+    pub method: &'a str,
+    /// The route template, e.g. `/api/tasks/:id`.
+    pub route: &'a str,
+    pub status: u16,
+    pub rpc_method: Option<&'a str>,
+    pub client_error: Option<&'a str>,
+    pub duration: Duration,
+}
+
+/// Incremented while alive, so a cancelled future still decrements.
+pub struct GaugeGuard<'a>(&'a IntGauge);
+
+impl<'a> GaugeGuard<'a> {
+    pub fn new(gauge: &'a IntGauge) -> Self {
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for GaugeGuard<'_> {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_record_request_rendered() -> Result<()> {
+        // -- Setup & Fixtures
+        let metrics = Metrics::new();
+        let fx_request = RequestMetrics {
+            method: "POST",
+            route: "/api/rpc",
+            status: 404,
+            rpc_method: Some("get_task"),
+            client_error: Some("ENTITY_NOT_FOUND"),
+            duration: Duration::from_millis(12),
+        };
+
+        // -- Exec
+        metrics.record_request(&fx_request);
+        metrics.record_rpc_call("list_task", None, Duration::from_millis(3));
+        {
+            let _waiting = GaugeGuard::new(&metrics.db_pool_waiters);
+            assert_eq!(metrics.db_pool_waiters.get(), 1);
+        }
+        let rendered = metrics.render();
+
+        // -- Check
+        assert!(rendered.contains(
+            r#"webapi_http_requests_total{client_error="ENTITY_NOT_FOUND",method="POST",route="/api/rpc",status="404"} 1"#
+        ), "{rendered}");
+        assert!(rendered.contains(
+            r#"webapi_rpc_requests_total{client_error="ENTITY_NOT_FOUND",rpc_method="get_task"} 1"#
+        ));
+        assert!(rendered.contains(r#"webapi_rpc_requests_total{client_error="",rpc_method="list_task"} 1"#));
+        assert!(rendered.contains("webapi_db_pool_waiters 0"));
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,383 @@
+use crate::ctx::Ctx;
+use crate::log::redactor;
+use crate::model::base::{CommonIden, Repository};
+use crate::model::{DbContext, Result};
+use modql::field::{Fields, HasFields};
+use schemars::JsonSchema;
+use sea_query::{Expr, Iden, Order, PostgresQueryBuilder, Query};
+use sea_query_binder::SqlxBinder;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use sqlx::{FromRow, PgConnection};
+use time::OffsetDateTime;
+
+/// The largest page, and the default one.
+pub const AUDIT_LIST_LIMIT: u64 = 200;
+
+// region: -- AuditEvent Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    LoginSuccess,
+    LoginFailure,
+    Logout,
+    PasswordChange,
+    /// The "log out everywhere", a new token salt.
+    TokensRevoke,
+    /// Also revokes the tokens of the user.
+    UserDisable,
+    UserEnable,
+    AdminGrant,
+    AdminRevoke,
+    // -- Through `base::*`
+    Create,
+    Update,
+    Delete,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::LoginSuccess => "login.success",
+            AuditAction::LoginFailure => "login.failure",
+            AuditAction::Logout => "logout",
+            AuditAction::PasswordChange => "user.password_change",
+            AuditAction::TokensRevoke => "user.tokens_revoke",
+            AuditAction::UserDisable => "user.disable",
+            AuditAction::UserEnable => "user.enable",
+            AuditAction::AdminGrant => "user.admin_grant",
+            AuditAction::AdminRevoke => "user.admin_revoke",
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct AuditEvent {
+    pub id: i64,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub ctime: OffsetDateTime,
+    pub actor_id: Option<i64>,
+    pub action: String,
+    pub entity: Option<String>,
+    pub entity_id: Option<i64>,
+    pub diff: Option<Value>,
+    pub detail: Option<Value>,
+    pub req_id: Option<String>,
+}
+
+/// Built from the `Ctx` acting, which gives the actor and the request id.
+pub struct AuditEventForCreate {
+    actor_id: Option<i64>,
+    action: AuditAction,
+    entity: Option<&'static str>,
+    entity_id: Option<i64>,
+    diff: Option<Value>,
+    detail: Option<Value>,
+    req_id: Option<String>,
+}
+
+impl AuditEventForCreate {
+    pub fn new(ctx: &Ctx, action: AuditAction) -> Self {
+        Self {
+            actor_id: Some(ctx.user_id()),
+            action,
+            entity: None,
+            entity_id: None,
+            diff: None,
+            detail: None,
+            req_id: ctx.req_id().map(str::to_string),
+        }
+    }
+
+    /// None when the actor is not known, e.g. a login with an unknown username.
+    pub fn actor(mut self, actor_id: Option<i64>) -> Self {
+        self.actor_id = actor_id;
+        self
+    }
+
+    pub fn entity(mut self, entity: &'static str, id: i64) -> Self {
+        self.entity = Some(entity);
+        self.entity_id = Some(id);
+        self
+    }
+
+    /// The rows as JSON, before and after the change. For an update, only
+    /// the changed columns are kept.
+    pub fn diff(mut self, before: Option<Value>, after: Option<Value>) -> Self {
+        let (before, after) = match (before, after) {
+            (Some(Value::Object(before)), Some(Value::Object(after))) => {
+                let (before, after) = changed_members(before, after);
+                (Some(Value::Object(before)), Some(Value::Object(after)))
+            }
+            other => other,
+        };
+        self.diff = Some(redactor().redact(json!({ "before": before, "after": after })));
+        self
+    }
+
+    pub fn detail(mut self, detail: Value) -> Self {
+        self.detail = Some(redactor().redact(detail));
+        self
+    }
+}
+
+fn changed_members(
+    mut before: Map<String, Value>,
+    mut after: Map<String, Value>,
+) -> (Map<String, Value>, Map<String, Value>) {
+    let unchanged: Vec<String> = before
+        .iter()
+        .filter(|(name, value)| after.get(*name) == Some(*value))
+        .map(|(name, _)| name.clone())
+        .collect();
+    for name in unchanged {
+        before.remove(&name);
+        after.remove(&name);
+    }
+
+    (before, after)
+}
+
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub actor_id: Option<i64>,
+    pub entity: Option<String>,
+    /// Inclusive.
+    pub from: Option<OffsetDateTime>,
+    /// Exclusive.
+    pub to: Option<OffsetDateTime>,
+    /// The cursor, the events older than this one (the last id of the
+    /// previous page).
+    pub before_id: Option<i64>,
+    /// At most `AUDIT_LIST_LIMIT`.
+    pub limit: Option<u64>,
+}
+
+#[derive(Iden)]
+enum AuditEventIden {
+    ActorId,
+    Entity,
+    Ctime,
+}
+
+// endregion: -- AuditEvent Types
+
+// region: -- AuditRepository
+
+/// Append-only, the table refuses the updates and deletes.
+pub struct AuditRepository;
+
+impl Repository for AuditRepository {
+    const TABLE: &'static str = "audit_event";
+}
+
+impl AuditRepository {
+    pub async fn record(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        event: AuditEventForCreate,
+    ) -> Result<()> {
+        let mut conn = db_context.db().acquire().await?;
+
+        Self::record_in(&mut conn, event).await
+    }
+
+    /// On the connection (or transaction) of the change audited, so both
+    /// are committed together.
+    pub async fn record_in(conn: &mut PgConnection, event: AuditEventForCreate) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_event (actor_id, action, entity, entity_id, diff, detail, req_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(event.actor_id)
+        .bind(event.action.as_str())
+        .bind(event.entity)
+        .bind(event.entity_id)
+        .bind(event.diff)
+        .bind(event.detail)
+        .bind(event.req_id)
+        .execute(conn)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Most recent first, a page of `filter.limit` events. The next page
+    /// starts before the last id of this one, a shorter page is the last.
+    pub async fn list(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        filter: AuditFilter,
+    ) -> Result<Vec<AuditEvent>> {
+        let db = db_context.db();
+
+        let mut query = Query::select();
+        query
+            .from(Self::table())
+            .columns(AuditEvent::field_column_refs())
+            .order_by(CommonIden::Id, Order::Desc)
+            .limit(filter.limit.unwrap_or(AUDIT_LIST_LIMIT).min(AUDIT_LIST_LIMIT));
+        if let Some(before_id) = filter.before_id {
+            query.and_where(Expr::col(CommonIden::Id).lt(before_id));
+        }
+        if let Some(actor_id) = filter.actor_id {
+            query.and_where(Expr::col(AuditEventIden::ActorId).eq(actor_id));
+        }
+        if let Some(entity) = filter.entity {
+            query.and_where(Expr::col(AuditEventIden::Entity).eq(entity));
+        }
+        if let Some(from) = filter.from {
+            query.and_where(Expr::col(AuditEventIden::Ctime).gte(from));
+        }
+        if let Some(to) = filter.to {
+            query.and_where(Expr::col(AuditEventIden::Ctime).lt(to));
+        }
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let events = sqlx::query_as_with::<_, AuditEvent, _>(&sql, values)
+            .fetch_all(db)
+            .await?;
+
+        Ok(events)
+    }
+}
+
+// endregion: -- AuditRepository
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::task::{TaskForCreate, TaskForUpdate, TaskRepository};
+    use crate::utils::time_utils::now_utc;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_base_changes_audited() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let fx_ctx = Ctx::new(9_001)?.with_req_id("fx-req-id");
+        let fx_from = now_utc();
+
+        // -- Exec
+        let task_c = TaskForCreate { title: "fx before".to_string() };
+        let id = TaskRepository::create(&fx_ctx, &db_context, task_c).await?;
+        let task_u = TaskForUpdate { title: Some("fx after".to_string()) };
+        TaskRepository::update(&fx_ctx, &db_context, id, task_u).await?;
+        TaskRepository::delete(&fx_ctx, &db_context, id).await?;
+
+        // -- Check
+        let filter = AuditFilter {
+            actor_id: Some(9_001),
+            entity: Some("task".to_string()),
+            from: Some(fx_from),
+            ..Default::default()
+        };
+        let events = AuditRepository::list(&Ctx::root_ctx(), &db_context, filter).await?;
+        let actions: Vec<&str> = events.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(actions, ["delete", "update", "create"]);
+        assert!(events.iter().all(|e| e.entity_id == Some(id) && e.req_id.as_deref() == Some("fx-req-id")));
+        assert_eq!(
+            events[1].diff,
+            Some(json!({ "before": { "title": "fx before" }, "after": { "title": "fx after" } })),
+            "Should only keep the changed columns"
+        );
+        assert_eq!(events[0].diff.as_ref().map(|d| &d["before"]["title"]), Some(&json!("fx after")));
+        assert_eq!(events[0].diff.as_ref().map(|d| &d["after"]), Some(&Value::Null));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_list_ok_paged() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let fx_ctx = Ctx::new(9_003)?;
+        for _ in 0..5 {
+            AuditRepository::record(&fx_ctx, &db_context, AuditEventForCreate::new(&fx_ctx, AuditAction::Logout)).await?;
+        }
+        let page = |before_id| AuditFilter {
+            actor_id: Some(9_003),
+            before_id,
+            limit: Some(2),
+            ..Default::default()
+        };
+
+        // -- Exec
+        let mut pages = Vec::new();
+        let mut before_id = None;
+        loop {
+            let events = AuditRepository::list(&Ctx::root_ctx(), &db_context, page(before_id)).await?;
+            let ids: Vec<i64> = events.iter().map(|e| e.id).collect();
+            before_id = ids.last().copied();
+            pages.push(ids);
+            if events.len() < 2 {
+                break;
+            }
+        }
+
+        // -- Check
+        let page_lens: Vec<usize> = pages.iter().map(Vec::len).collect();
+        assert_eq!(page_lens, [2, 2, 1]);
+        let ids: Vec<i64> = pages.concat();
+        assert!(ids.windows(2).all(|w| w[0] > w[1]), "Should be most recent first, no repeat: {ids:?}");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_audit_event_append_only() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let fx_ctx = Ctx::new(9_002)?;
+        AuditRepository::record(&fx_ctx, &db_context, AuditEventForCreate::new(&fx_ctx, AuditAction::Logout)).await?;
+
+        // -- Exec
+        let update_res = sqlx::query("UPDATE audit_event SET action = 'x' WHERE actor_id = 9002")
+            .execute(db_context.db())
+            .await;
+        let delete_res = sqlx::query("DELETE FROM audit_event WHERE actor_id = 9002")
+            .execute(db_context.db())
+            .await;
+
+        // -- Check
+        assert!(update_res.is_err(), "Should refuse the update");
+        assert!(delete_res.is_err(), "Should refuse the delete");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_redacted() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_before = json!({ "id": 1, "username": "demo1", "pwd": "fx-hash-1", "disabled": false });
+        let fx_after = json!({ "id": 1, "username": "demo1", "pwd": "fx-hash-2", "disabled": true });
+
+        // -- Exec
+        let event = AuditEventForCreate::new(&Ctx::root_ctx(), AuditAction::Update)
+            .diff(Some(fx_before), Some(fx_after));
+
+        // -- Check
+        assert_eq!(
+            event.diff,
+            Some(json!({
+                "before": { "pwd": "[REDACTED]", "disabled": false },
+                "after": { "pwd": "[REDACTED]", "disabled": true },
+            }))
+        );
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -1,14 +1,20 @@
 use crate::ctx::Ctx;
+use crate::model::audit::{AuditAction, AuditEventForCreate, AuditRepository};
 use crate::model::DbContext;
+use crate::metrics::metrics;
+use crate::model::store;
 use crate::model::{Error, Result};
 use modql::field::HasFields;
 use modql::SIden;
 use sea_query::{Expr, Iden, IntoIden, PostgresQueryBuilder, Query, TableRef};
 use sea_query_binder::SqlxBinder;
 use serde::de::value;
+use serde_json::Value;
 use sqlx::postgres::PgRow;
-use sqlx::FromRow;
+use sqlx::{Connection, FromRow, PgConnection};
+use std::future::Future;
 use std::mem::take;
+use tracing::{info_span, Instrument};
 
 #[derive(Iden)]
 pub enum CommonIden {
@@ -23,8 +29,9 @@ pub trait Repository {
     }
 }
 
+/// Audited, as every change made through `base`.
 pub async fn create<EntityRepository, Entity>(
-    _ctx: &Ctx,
+    ctx: &Ctx,
     mm: &DbContext,
     entity: Entity,
 ) -> Result<i64>
@@ -32,8 +39,24 @@ where
     EntityRepository: Repository,
     Entity: HasFields,
 {
-    let db = mm.db();
+    let mut conn = store::acquire(mm.db()).await?;
+    let mut tx = conn.begin().await?;
+    let id = create_in::<EntityRepository, _>(ctx, &mut tx, entity).await?;
+    tx.commit().await?;
+
+    Ok(id)
+}
 
+/// For a create part of a larger transaction, committed by the caller.
+pub async fn create_in<EntityRepository, Entity>(
+    ctx: &Ctx,
+    conn: &mut PgConnection,
+    entity: Entity,
+) -> Result<i64>
+where
+    EntityRepository: Repository,
+    Entity: HasFields,
+{
     let fields = entity.not_none_fields();
     let (columns, sea_values) = fields.for_sea_insert();
 
@@ -45,9 +68,14 @@ where
         .returning(Query::returning().columns([CommonIden::Id]));
 
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let (id,) = sqlx::query_as_with::<_, (i64,), _>(&sql, values)
-        .fetch_one(db)
-        .await?;
+    let query = sqlx::query_as_with::<_, (i64,), _>(&sql, values).fetch_one(&mut *conn);
+    let (id,) = run_query("create", EntityRepository::TABLE, query).await?;
+
+    let after = row_snapshot::<EntityRepository>(conn, id).await?;
+    let event = AuditEventForCreate::new(ctx, AuditAction::Create)
+        .entity(EntityRepository::TABLE, id)
+        .diff(None, after);
+    AuditRepository::record_in(conn, event).await?;
 
     Ok(id)
 }
@@ -62,7 +90,7 @@ where
     Entity: for<'r> FromRow<'r, PgRow> + Unpin + Send,
     Entity: HasFields,
 {
-    let db = db_context.db();
+    let mut conn = store::acquire(db_context.db()).await?;
 
     let mut query = Query::select();
     query
@@ -71,12 +99,12 @@ where
         .and_where(Expr::col(CommonIden::Id).eq(id));
 
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let entity = sqlx::query_as_with::<_, Entity, _>(&sql, values)
-        .fetch_optional(db)
+    let query = sqlx::query_as_with::<_, Entity, _>(&sql, values).fetch_optional(&mut *conn);
+    let entity = run_query("get", EntityRepository::TABLE, query)
         .await?
         .ok_or(Error::EntityNotFound {
             entity: EntityRepository::TABLE,
-            id: id,
+            id,
         })?;
 
     Ok(entity)
@@ -91,7 +119,7 @@ where
     Entity: for<'r> FromRow<'r, PgRow> + Unpin + Send,
     Entity: HasFields,
 {
-    let db = db_context.db();
+    let mut conn = store::acquire(db_context.db()).await?;
 
     let mut query = Query::select();
     query
@@ -99,15 +127,14 @@ where
         .columns(Entity::field_column_refs());
 
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let entities = sqlx::query_as_with::<_, Entity, _>(&sql, values)
-        .fetch_all(db)
-        .await?;
+    let query = sqlx::query_as_with::<_, Entity, _>(&sql, values).fetch_all(&mut *conn);
+    let entities = run_query("list", EntityRepository::TABLE, query).await?;
 
     Ok(entities)
 }
 
 pub async fn update<EntityRepository, Entity>(
-    _ctx: &Ctx,
+    ctx: &Ctx,
     mm: &DbContext,
     id: i64,
     entity: Entity,
@@ -116,7 +143,10 @@ where
     EntityRepository: Repository,
     Entity: HasFields,
 {
-    let db = mm.db();
+    let mut conn = store::acquire(mm.db()).await?;
+    let mut tx = conn.begin().await?;
+
+    let before = row_snapshot::<EntityRepository>(&mut tx, id).await?;
 
     let fields = entity.not_none_fields();
     let fields = fields.for_sea_update();
@@ -128,31 +158,40 @@ where
         .and_where(Expr::col(CommonIden::Id).eq(id));
 
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let count = sqlx::query_with(&sql, values)
-        .execute(db)
+    let query = sqlx::query_with(&sql, values).execute(&mut *tx);
+    let count = run_query("update", EntityRepository::TABLE, query)
         .await?
         .rows_affected();
 
     if count == 0 {
-        Err(Error::EntityNotFound {
+        return Err(Error::EntityNotFound {
             entity: EntityRepository::TABLE,
             id,
-        })
-    } else {
-        Ok(())
+        });
     }
+
+    let after = row_snapshot::<EntityRepository>(&mut tx, id).await?;
+    let event = AuditEventForCreate::new(ctx, AuditAction::Update)
+        .entity(EntityRepository::TABLE, id)
+        .diff(before, after);
+    AuditRepository::record_in(&mut tx, event).await?;
+    tx.commit().await?;
+
+    Ok(())
 }
 
-pub async fn delete<EntityRepository, Entity>(
-    _ctx: &Ctx,
+pub async fn delete<EntityRepository>(
+    ctx: &Ctx,
     db_context: &DbContext,
     id: i64,
 ) -> Result<()>
 where
     EntityRepository: Repository,
-    Entity: HasFields,
 {
-    let db = db_context.db();
+    let mut conn = store::acquire(db_context.db()).await?;
+    let mut tx = conn.begin().await?;
+
+    let before = row_snapshot::<EntityRepository>(&mut tx, id).await?;
 
     let mut query = Query::delete();
     query
@@ -160,12 +199,11 @@ where
         .and_where(Expr::col(CommonIden::Id).eq(id));
 
     let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-    let count = sqlx::query_with(&sql, values)
-        .execute(db)
+    let query = sqlx::query_with(&sql, values).execute(&mut *tx);
+    let count = run_query("delete", EntityRepository::TABLE, query)
         .await?
         .rows_affected();
 
-
     if count == 0 {
         return Err(Error::EntityNotFound {
             entity: EntityRepository::TABLE,
@@ -173,5 +211,40 @@ where
         });
     }
 
+    let event = AuditEventForCreate::new(ctx, AuditAction::Delete)
+        .entity(EntityRepository::TABLE, id)
+        .diff(before, None);
+    AuditRepository::record_in(&mut tx, event).await?;
+    tx.commit().await?;
+
     Ok(())
 }
+
+/// The whole row as JSON, for the audit diff. Locked until the end of the
+/// transaction, so the diff matches the change.
+async fn row_snapshot<EntityRepository>(conn: &mut PgConnection, id: i64) -> Result<Option<Value>>
+where
+    EntityRepository: Repository,
+{
+    let sql = format!(
+        r#"SELECT to_jsonb(t) FROM "{}" t WHERE id = $1 FOR UPDATE"#,
+        EntityRepository::TABLE
+    );
+    let row: Option<(Value,)> = sqlx::query_as(&sql).bind(id).fetch_optional(conn).await?;
+
+    Ok(row.map(|(row,)| row))
+}
+
+/// Times the query, in a child span of the request.
+async fn run_query<F: Future>(op: &'static str, table: &'static str, query: F) -> F::Output {
+    let span = info_span!(
+        "db.query",
+        otel.name = format!("{op} {table}"),
+        otel.kind = "client",
+        db.system = "postgresql",
+        db.operation = op,
+        db.sql.table = table,
+    );
+
+    metrics().time_query(op, table, query).instrument(span).await
+}
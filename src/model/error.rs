@@ -1,12 +1,20 @@
-use crate::model::store;
-use crate::pwd;
+use crate::model::{migration, store};
+use crate::{event, pwd};
 use serde::Serialize;
 use serde_with::{serde_as, DisplayFromStr};
+use sqlx::postgres::PgDatabaseError;
 use std::fmt::Formatter;
 use derive_more::From;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
+// Postgres SQLSTATE codes, see https://www.postgresql.org/docs/current/errcodes-appendix.html
+const PG_UNIQUE_VIOLATION: &str = "23505";
+const PG_FOREIGN_KEY_VIOLATION: &str = "23503";
+const PG_NOT_NULL_VIOLATION: &str = "23502";
+const PG_CHECK_VIOLATION: &str = "23514";
+const PG_STRING_DATA_RIGHT_TRUNCATION: &str = "22001";
+
 #[serde_as]
 #[derive(Debug, Serialize, From)]
 pub enum Error {
@@ -14,16 +22,55 @@ pub enum Error {
     Pwd(pwd::Error),
     #[from]
     Store(store::Error),
+    #[from]
+    Event(event::Error),
+    #[from]
+    Migration(migration::Error),
 
     EntityNotFound { entity: &'static str, id: i64 },
-    TicketDeleteIdNotFound { id: u64 },
+    InvalidState { entity: &'static str, id: i64, state: String },
+    /// The lease of a claimed row expired, the row may have been claimed again.
+    LeaseLost { entity: &'static str, id: i64 },
+
+    // -- Constraint violations (mapped from the Postgres error codes)
+    UniqueViolation { table: Option<String>, constraint: Option<String> },
+    ForeignKeyViolation { table: Option<String>, constraint: Option<String> },
+    NotNullViolation { table: Option<String>, column: Option<String> },
+    CheckViolation { table: Option<String>, constraint: Option<String> },
+    /// Postgres names neither the table nor the column of this error.
+    StringTooLong { message: String },
 
-    #[from]
     Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
     #[from]
     SeaQuery(#[serde_as(as = "DisplayFromStr")] sea_query::error::Error)
 }
 
+impl From<sqlx::Error> for Error {
+    fn from(ex: sqlx::Error) -> Self {
+        let Some(pg_err) = ex
+            .as_database_error()
+            .and_then(|db_err| db_err.try_downcast_ref::<PgDatabaseError>())
+        else {
+            return Error::Sqlx(ex);
+        };
+
+        let table = pg_err.table().map(str::to_string);
+        let column = pg_err.column().map(str::to_string);
+        let constraint = pg_err.constraint().map(str::to_string);
+
+        match pg_err.code() {
+            PG_UNIQUE_VIOLATION => Error::UniqueViolation { table, constraint },
+            PG_FOREIGN_KEY_VIOLATION => Error::ForeignKeyViolation { table, constraint },
+            PG_NOT_NULL_VIOLATION => Error::NotNullViolation { table, column },
+            PG_CHECK_VIOLATION => Error::CheckViolation { table, constraint },
+            PG_STRING_DATA_RIGHT_TRUNCATION => Error::StringTooLong {
+                message: pg_err.message().to_string(),
+            },
+            _ => Error::Sqlx(ex),
+        }
+    }
+}
+
 impl core::fmt::Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{:?}", self)
@@ -0,0 +1,412 @@
+use crate::ctx::Ctx;
+use crate::model::base::{self, CommonIden, Repository};
+use crate::model::{DbContext, Error, Result};
+use modql::field::{Fields, HasFields};
+use schemars::JsonSchema;
+use sea_query::{Expr, Iden, Order, PostgresQueryBuilder, Query, UpdateStatement};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+
+const JOB_LIST_LIMIT: u64 = 200;
+
+// region: -- Job Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    /// Out of attempts, only runs again through `JobRepository::retry`.
+    Dead,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Dead => "dead",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct Job {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub run_at: OffsetDateTime,
+    /// The lease of a running job, set by `claim_next`.
+    #[serde(with = "time::serde::rfc3339::option")]
+    #[schemars(with = "Option<String>")]
+    pub locked_until: Option<OffsetDateTime>,
+    pub last_error: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub ctime: OffsetDateTime,
+}
+
+#[derive(Fields)]
+pub struct JobForCreate {
+    pub kind: String,
+    pub payload: Value,
+    pub max_attempts: i32,
+    pub run_at: OffsetDateTime,
+}
+
+#[derive(Iden)]
+enum JobIden {
+    Kind,
+    Status,
+    Attempts,
+    RunAt,
+    LockedUntil,
+    LastError,
+}
+
+// endregion: -- Job Types
+
+// region: -- JobRepository
+
+pub struct JobRepository;
+
+impl Repository for JobRepository {
+    const TABLE: &'static str = "job";
+}
+
+impl JobRepository {
+    pub async fn create(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        job_c: JobForCreate,
+    ) -> Result<i64> {
+        base::create::<Self, _>(ctx, db_context, job_c).await
+    }
+
+    pub async fn get(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+    ) -> Result<Job> {
+        base::get::<Self, _>(ctx, db_context, id).await
+    }
+
+    /// Most recent first, capped to the last `JOB_LIST_LIMIT` jobs.
+    pub async fn list(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        status: Option<JobStatus>,
+    ) -> Result<Vec<Job>> {
+        let db = db_context.db();
+
+        let mut query = Query::select();
+        query
+            .from(Self::table())
+            .columns(Job::field_column_refs())
+            .order_by(CommonIden::Id, Order::Desc)
+            .limit(JOB_LIST_LIMIT);
+        if let Some(status) = status {
+            query.and_where(Expr::col(JobIden::Status).eq(status.as_str()));
+        }
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let jobs = sqlx::query_as_with::<_, Job, _>(&sql, values)
+            .fetch_all(db)
+            .await?;
+
+        Ok(jobs)
+    }
+
+    /// Whether a job of the `kind` waits to run, e.g. to schedule it once.
+    pub async fn has_queued(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        kind: &str,
+    ) -> Result<bool> {
+        let (queued,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS (SELECT 1 FROM job WHERE kind = $1 AND status = 'queued')",
+        )
+        .bind(kind)
+        .fetch_one(db_context.db())
+        .await?;
+
+        Ok(queued)
+    }
+
+    /// Deletes the done jobs created more than `keep_days` ago, the dead
+    /// ones are kept for `retry`.
+    pub async fn purge_done(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        keep_days: i32,
+    ) -> Result<u64> {
+        let res = sqlx::query(
+            "DELETE FROM job WHERE status = 'done' AND ctime < now() - make_interval(days => $1)",
+        )
+        .bind(keep_days)
+        .execute(db_context.db())
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Claims the next due job of one of the `kinds`, counting it as an
+    /// attempt. The job is leased for `lease_sec`: if the worker dies, it is
+    /// picked again once the lease expires.
+    pub async fn claim_next(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        kinds: &[String],
+        lease_sec: f64,
+    ) -> Result<Option<Job>> {
+        let db = db_context.db();
+
+        let job = sqlx::query_as::<_, Job>(
+            r#"
+            WITH due AS (
+                SELECT id
+                FROM job
+                WHERE kind = ANY($1)
+                  AND ((status = 'queued' AND run_at <= now())
+                    OR (status = 'running' AND locked_until < now()))
+                ORDER BY run_at
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            UPDATE job
+            SET status = 'running',
+                attempts = job.attempts + 1,
+                locked_until = now() + make_interval(secs => $2)
+            FROM due
+            WHERE job.id = due.id
+            RETURNING job.id, job.kind, job.payload, job.status, job.attempts,
+                      job.max_attempts, job.run_at, job.locked_until, job.last_error, job.ctime
+            "#,
+        )
+        .bind(kinds)
+        .bind(lease_sec)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// `LeaseLost` once the lease of the claimed `job` expired.
+    pub async fn complete(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        job: &Job,
+    ) -> Result<()> {
+        let db = db_context.db();
+
+        let mut query = Query::update();
+        query
+            .table(Self::table())
+            .value(JobIden::Status, JobStatus::Done.as_str())
+            .value(JobIden::LockedUntil, Option::<OffsetDateTime>::None)
+            .value(JobIden::LastError, Option::<String>::None);
+        Self::and_where_leased(&mut query, job);
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let count = sqlx::query_with(&sql, values).execute(db).await?.rows_affected();
+
+        Self::check_leased(count, job)
+    }
+
+    /// Re-queues the claimed `job` in `retry_in_sec`, or dead-letters it
+    /// when `None`. `LeaseLost` once its lease expired.
+    pub async fn fail(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        job: &Job,
+        error: String,
+        retry_in_sec: Option<f64>,
+    ) -> Result<()> {
+        let db = db_context.db();
+
+        let mut query = Query::update();
+        query
+            .table(Self::table())
+            .value(JobIden::LockedUntil, Option::<OffsetDateTime>::None)
+            .value(JobIden::LastError, error);
+        Self::and_where_leased(&mut query, job);
+        match retry_in_sec {
+            Some(retry_in_sec) => {
+                query
+                    .value(JobIden::Status, JobStatus::Queued.as_str())
+                    .value(
+                        JobIden::RunAt,
+                        Expr::cust_with_values("now() + make_interval(secs => $1)", [retry_in_sec]),
+                    );
+            }
+            None => {
+                query.value(JobIden::Status, JobStatus::Dead.as_str());
+            }
+        }
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let count = sqlx::query_with(&sql, values).execute(db).await?.rows_affected();
+
+        Self::check_leased(count, job)
+    }
+
+    /// A claim of another worker, once the lease expired, set another `locked_until`.
+    fn and_where_leased(query: &mut UpdateStatement, job: &Job) {
+        query
+            .and_where(Expr::col(CommonIden::Id).eq(job.id))
+            .and_where(Expr::col(JobIden::Status).eq(JobStatus::Running.as_str()))
+            .and_where(Expr::col(JobIden::LockedUntil).eq(job.locked_until));
+    }
+
+    fn check_leased(count: u64, job: &Job) -> Result<()> {
+        if count == 0 {
+            return Err(Error::LeaseLost { entity: Self::TABLE, id: job.id });
+        }
+
+        Ok(())
+    }
+
+    /// Runs a dead (or still queued) job again now, with a fresh attempt count.
+    pub async fn retry(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+    ) -> Result<()> {
+        let db = db_context.db();
+
+        let mut query = Query::update();
+        query
+            .table(Self::table())
+            .value(JobIden::Status, JobStatus::Queued.as_str())
+            .value(JobIden::Attempts, 0)
+            .value(JobIden::RunAt, Expr::cust("now()"))
+            .and_where(Expr::col(CommonIden::Id).eq(id))
+            .and_where(
+                Expr::col(JobIden::Status)
+                    .is_in([JobStatus::Dead.as_str(), JobStatus::Queued.as_str()]),
+            );
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let count = sqlx::query_with(&sql, values)
+            .execute(db)
+            .await?
+            .rows_affected();
+
+        if count == 0 {
+            // Either missing, or running/done.
+            let job = Self::get(ctx, db_context, id).await?;
+            return Err(Error::InvalidState { entity: Self::TABLE, id, state: job.status });
+        }
+
+        Ok(())
+    }
+}
+
+// endregion: -- JobRepository
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use anyhow::Result;
+    use serde_json::json;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_claim_next_skips_scheduled_and_other_kinds() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_kinds = vec!["test_claim_next".to_string()];
+        let fx_now = OffsetDateTime::now_utc();
+        let later_id = JobRepository::create(&ctx, &db_context, JobForCreate {
+            kind: "test_claim_next".to_string(),
+            payload: json!({"n": 1}),
+            max_attempts: 3,
+            run_at: fx_now + time::Duration::hours(1),
+        }).await?;
+        JobRepository::create(&ctx, &db_context, JobForCreate {
+            kind: "test_claim_next_other".to_string(),
+            payload: json!({"n": 2}),
+            max_attempts: 3,
+            run_at: fx_now,
+        }).await?;
+        let due_id = JobRepository::create(&ctx, &db_context, JobForCreate {
+            kind: "test_claim_next".to_string(),
+            payload: json!({"n": 3}),
+            max_attempts: 3,
+            run_at: fx_now,
+        }).await?;
+
+        // -- Exec
+        let claimed = JobRepository::claim_next(&ctx, &db_context, &fx_kinds, 30.).await?;
+        let claimed_again = JobRepository::claim_next(&ctx, &db_context, &fx_kinds, 30.).await?;
+
+        // -- Check
+        let claimed = claimed.expect("Should have claimed the due job");
+        assert_eq!(claimed.id, due_id);
+        assert_eq!(claimed.status, "running");
+        assert_eq!(claimed.attempts, 1);
+        assert!(claimed_again.is_none(), "Scheduled job should not be claimed yet");
+
+        let res = JobRepository::retry(&ctx, &db_context, due_id).await;
+        assert!(
+            matches!(res, Err(Error::InvalidState { entity: "job", .. })),
+            "A running job should not be retryable"
+        );
+        JobRepository::retry(&ctx, &db_context, later_id).await?;
+        let claimed = JobRepository::claim_next(&ctx, &db_context, &fx_kinds, 30.).await?;
+        assert_eq!(claimed.map(|j| j.id), Some(later_id));
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_complete_err_lease_lost() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_kinds = vec!["test_complete_err_lease_lost".to_string()];
+        let id = JobRepository::create(&ctx, &db_context, JobForCreate {
+            kind: "test_complete_err_lease_lost".to_string(),
+            payload: json!({}),
+            max_attempts: 3,
+            run_at: OffsetDateTime::now_utc(),
+        }).await?;
+
+        // -- Exec
+        // The first lease expires at once, the job is claimed again by another worker.
+        let first = JobRepository::claim_next(&ctx, &db_context, &fx_kinds, 0.).await?.expect("Should claim");
+        let second = JobRepository::claim_next(&ctx, &db_context, &fx_kinds, 60.).await?.expect("Should claim again");
+        let res_complete = JobRepository::complete(&ctx, &db_context, &first).await;
+        let res_fail = JobRepository::fail(&ctx, &db_context, &first, "late".to_string(), None).await;
+        JobRepository::complete(&ctx, &db_context, &second).await?;
+
+        // -- Check
+        assert_eq!(second.id, id);
+        assert!(
+            matches!(res_complete, Err(Error::LeaseLost { entity: "job", .. })),
+            "Should have lost the lease, was `{res_complete:?}`"
+        );
+        assert!(matches!(res_fail, Err(Error::LeaseLost { .. })), "was `{res_fail:?}`");
+        let job = JobRepository::get(&ctx, &db_context, id).await?;
+        assert_eq!(job.status, "done");
+        assert_eq!(job.attempts, 2);
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,34 @@
+use std::fmt::Formatter;
+use serde::Serialize;
+use serde_with::{serde_as, DisplayFromStr};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[serde_as]
+#[derive(Debug, Serialize)]
+pub enum Error {
+    /// An applied migration file was edited, migrations are append-only.
+    ChecksumMismatch { version: i64, name: String },
+    MigrationFail {
+        version: i64,
+        name: String,
+        #[serde_as(as = "DisplayFromStr")]
+        cause: sqlx::Error,
+    },
+
+    Sqlx(#[serde_as(as = "DisplayFromStr")] sqlx::Error),
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(ex: sqlx::Error) -> Self {
+        Error::Sqlx(ex)
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
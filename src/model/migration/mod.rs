@@ -0,0 +1,269 @@
+mod error;
+mod splitter;
+
+pub use self::error::{Error, Result};
+pub(crate) use self::splitter::split_statements;
+
+use sha2::{Digest, Sha256};
+use sqlx::pool::PoolConnection;
+use sqlx::{Connection, FromRow, Postgres};
+use time::OffsetDateTime;
+use tracing::{info, warn};
+use crate::model::DbContext;
+use crate::utils::base64_utils::b64u_encode;
+
+/// Arbitrary, shared by every instance so only one migrates at a time.
+const MIGRATION_LOCK_KEY: i64 = 0x5745_4241_5049;
+
+const SQL_CREATE_MIGRATIONS_TABLE: &str = r#"
+CREATE TABLE IF NOT EXISTS _migrations (
+    version BIGINT PRIMARY KEY,
+    name varchar(256) NOT NULL,
+    checksum varchar(64) NOT NULL,
+    applied_at timestamptz NOT NULL DEFAULT now()
+)"#;
+
+const SQL_SELECT_APPLIED: &str =
+    "SELECT version, checksum, applied_at FROM _migrations ORDER BY version";
+
+macro_rules! migration {
+    ($version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            sql: include_str!(concat!("../../../sql/migrations/", $name, ".sql")),
+        }
+    };
+}
+
+/// Embedded in the binary, in order. Append-only: an applied file must not
+/// change, a schema change is a new file.
+const MIGRATIONS: &[Migration] = &[
+    migration!(1, "0001_create_user_task"),
+    migration!(2, "0002_create_ticket"),
+    migration!(3, "0003_create_webhook"),
+    migration!(4, "0004_create_job"),
+    migration!(5, "0005_user_disabled"),
+    migration!(6, "0006_audit_event"),
+    migration!(7, "0007_rate_limit_bucket"),
+    migration!(8, "0008_user_admin"),
+];
+
+// region: -- Types
+
+#[derive(Debug)]
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    sql: &'static str,
+}
+
+impl Migration {
+    fn checksum(&self) -> String {
+        b64u_encode(Sha256::digest(self.sql.as_bytes()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Pending,
+    Applied,
+    ChecksumMismatch,
+}
+
+#[derive(Debug)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: &'static str,
+    pub state: MigrationState,
+    pub applied_at: Option<OffsetDateTime>,
+}
+
+#[derive(FromRow)]
+struct AppliedMigration {
+    version: i64,
+    checksum: String,
+    applied_at: OffsetDateTime,
+}
+
+// endregion: -- Types
+
+/// Applies the pending migrations, each in its own transaction.
+/// Nothing is applied if an already applied migration was modified.
+pub async fn migrate_up(db_context: &DbContext) -> Result<Vec<&'static Migration>> {
+    let mut conn = db_context.db().acquire().await?;
+
+    // Waiting for the lock or migrating may take longer than `DB_STATEMENT_TIMEOUT_SEC`.
+    sqlx::query("SET statement_timeout = 0").execute(&mut *conn).await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    let res = apply_pending(&mut conn).await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+    // Back to the connection default, before returning it to the pool.
+    sqlx::query("RESET statement_timeout").execute(&mut *conn).await?;
+
+    res
+}
+
+pub async fn status(db_context: &DbContext) -> Result<Vec<MigrationStatus>> {
+    let mut conn = db_context.db().acquire().await?;
+
+    let applied = load_applied(&mut conn).await?;
+
+    let statuses = MIGRATIONS
+        .iter()
+        .map(|migration| {
+            let applied = applied.iter().find(|a| a.version == migration.version);
+            let state = match applied {
+                None => MigrationState::Pending,
+                Some(a) if a.checksum == migration.checksum() => MigrationState::Applied,
+                Some(_) => MigrationState::ChecksumMismatch,
+            };
+
+            MigrationStatus {
+                version: migration.version,
+                name: migration.name,
+                state,
+                applied_at: applied.map(|a| a.applied_at),
+            }
+        })
+        .collect();
+
+    Ok(statuses)
+}
+
+/// Whether every migration is applied, unchanged. Read only, unlike `status`.
+pub async fn is_up_to_date(db_context: &DbContext) -> Result<bool> {
+    let applied = sqlx::query_as::<_, AppliedMigration>(SQL_SELECT_APPLIED)
+        .fetch_all(db_context.db())
+        .await?;
+
+    let up_to_date = MIGRATIONS.iter().all(|migration| {
+        applied
+            .iter()
+            .any(|a| a.version == migration.version && a.checksum == migration.checksum())
+    });
+
+    Ok(up_to_date)
+}
+
+async fn apply_pending(conn: &mut PoolConnection<Postgres>) -> Result<Vec<&'static Migration>> {
+    let applied = load_applied(conn).await?;
+
+    // -- Check the applied ones first, before changing anything.
+    for migration in MIGRATIONS {
+        let Some(applied) = applied.iter().find(|a| a.version == migration.version) else {
+            continue;
+        };
+        if applied.checksum != migration.checksum() {
+            return Err(Error::ChecksumMismatch {
+                version: migration.version,
+                name: migration.name.to_string(),
+            });
+        }
+    }
+    if let Some(unknown) = applied.iter().find(|a| MIGRATIONS.iter().all(|m| m.version != a.version)) {
+        warn!("{:<12} - database has unknown migration {}, older binary?", "MIGRATION", unknown.version);
+    }
+
+    // -- Apply the pending ones, in order.
+    let mut newly_applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| applied.iter().all(|a| a.version != m.version)) {
+        apply(conn, migration).await?;
+        info!("{:<12} - applied {}", "MIGRATION", migration.name);
+
+        newly_applied.push(migration);
+    }
+
+    Ok(newly_applied)
+}
+
+async fn apply(conn: &mut PoolConnection<Postgres>, migration: &Migration) -> Result<()> {
+    let fail = |cause| Error::MigrationFail {
+        version: migration.version,
+        name: migration.name.to_string(),
+        cause,
+    };
+
+    let mut tx = conn.begin().await?;
+
+    for statement in split_statements(migration.sql) {
+        sqlx::query(&statement).execute(&mut *tx).await.map_err(fail)?;
+    }
+
+    sqlx::query("INSERT INTO _migrations (version, name, checksum) VALUES ($1, $2, $3)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(migration.checksum())
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await.map_err(fail)?;
+
+    Ok(())
+}
+
+async fn load_applied(conn: &mut PoolConnection<Postgres>) -> Result<Vec<AppliedMigration>> {
+    sqlx::query(SQL_CREATE_MIGRATIONS_TABLE).execute(&mut **conn).await?;
+
+    let applied = sqlx::query_as::<_, AppliedMigration>(SQL_SELECT_APPLIED)
+        .fetch_all(&mut **conn)
+        .await?;
+
+    Ok(applied)
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_migrate_up_err_checksum_mismatch() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let fx_migration = &MIGRATIONS[0];
+
+        // -- Exec
+        let applied_again = migrate_up(&db_context).await?;
+        sqlx::query("UPDATE _migrations SET checksum = 'tampered' WHERE version = $1")
+            .bind(fx_migration.version)
+            .execute(db_context.db())
+            .await?;
+        let res = migrate_up(&db_context).await;
+        let statuses = status(&db_context).await?;
+        let up_to_date = is_up_to_date(&db_context).await?;
+
+        // -- Clean
+        sqlx::query("UPDATE _migrations SET checksum = $1 WHERE version = $2")
+            .bind(fx_migration.checksum())
+            .bind(fx_migration.version)
+            .execute(db_context.db())
+            .await?;
+
+        // -- Check
+        assert!(applied_again.is_empty(), "Dev init should have applied everything");
+        assert!(
+            matches!(res, Err(Error::ChecksumMismatch { version: 1, .. })),
+            "Should refuse to migrate, got {res:?}",
+        );
+        assert_eq!(statuses[0].state, MigrationState::ChecksumMismatch);
+        assert!(statuses[1..].iter().all(|s| s.state == MigrationState::Applied));
+        assert!(!up_to_date, "A modified migration is not up to date");
+        assert!(is_up_to_date(&db_context).await?);
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
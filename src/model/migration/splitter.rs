@@ -0,0 +1,210 @@
+/// Splits a SQL script on the `;` ending each statement, ignoring the ones
+/// inside string literals, quoted identifiers, comments and dollar-quoted
+/// (`$$ ... $$`, `$tag$ ... $tag$`) bodies. Empty statements are dropped.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => i = skip_quoted(bytes, i, b'\'', is_escape_string(bytes, i)),
+            b'"' => i = skip_quoted(bytes, i, b'"', false),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line_comment(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i),
+            b'$' => i = skip_dollar_quoted(sql, i),
+            b';' => {
+                push_statement(&mut statements, &sql[start..i]);
+                i += 1;
+                start = i;
+            }
+            _ => i += 1,
+        }
+    }
+    push_statement(&mut statements, &sql[start..]);
+
+    statements
+}
+
+fn push_statement(statements: &mut Vec<String>, statement: &str) {
+    if has_code(statement) {
+        statements.push(statement.trim().to_string());
+    }
+}
+
+/// Whether there is something else than whitespace and comments.
+fn has_code(statement: &str) -> bool {
+    let bytes = statement.as_bytes();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_line_comment(bytes, i),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_block_comment(bytes, i),
+            b if b.is_ascii_whitespace() => i += 1,
+            _ => return true,
+        }
+    }
+
+    false
+}
+
+/// `E'...'` strings, where a backslash escapes the next character.
+fn is_escape_string(bytes: &[u8], quote: usize) -> bool {
+    let prefixed = quote > 0 && matches!(bytes[quote - 1], b'E' | b'e');
+    let standalone = quote < 2 || !is_ident_byte(bytes[quote - 2]);
+
+    prefixed && standalone
+}
+
+/// Returns the index after the closing quote, a doubled quote is an escaped one.
+fn skip_quoted(bytes: &[u8], open: usize, quote: u8, backslash_escapes: bool) -> usize {
+    let mut i = open + 1;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if backslash_escapes => i += 2,
+            b if b == quote && bytes.get(i + 1) == Some(&quote) => i += 2,
+            b if b == quote => return i + 1,
+            _ => i += 1,
+        }
+    }
+
+    bytes.len()
+}
+
+fn skip_line_comment(bytes: &[u8], start: usize) -> usize {
+    bytes[start..]
+        .iter()
+        .position(|b| *b == b'\n')
+        .map_or(bytes.len(), |pos| start + pos + 1)
+}
+
+/// Postgres block comments nest.
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0;
+    let mut i = start;
+
+    while i < bytes.len() {
+        if bytes[i] == b'/' && bytes.get(i + 1) == Some(&b'*') {
+            depth += 1;
+            i += 2;
+        } else if bytes[i] == b'*' && bytes.get(i + 1) == Some(&b'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+
+    bytes.len()
+}
+
+/// A `$` only opens a dollar quote when followed by an optional tag and a
+/// `$`, and not when part of an identifier or a `$1` parameter.
+fn skip_dollar_quoted(sql: &str, start: usize) -> usize {
+    let bytes = sql.as_bytes();
+
+    if start > 0 && is_ident_byte(bytes[start - 1]) {
+        return start + 1;
+    }
+
+    let tag_len = bytes[start + 1..]
+        .iter()
+        .enumerate()
+        .take_while(|(idx, b)| is_ident_byte(**b) && !(*idx == 0 && b.is_ascii_digit()))
+        .count();
+    if bytes.get(start + 1 + tag_len) != Some(&b'$') {
+        return start + 1;
+    }
+
+    let delimiter = &sql[start..start + tag_len + 2];
+    let body_start = start + delimiter.len();
+
+    sql[body_start..]
+        .find(delimiter)
+        .map_or(bytes.len(), |pos| body_start + pos + delimiter.len())
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_split_statements_simple() -> Result<()> {
+        // -- Fixtures
+        let fx_sql = "CREATE TABLE a (id INT);\n\n-- comment; not a statement\nINSERT INTO a VALUES (1);\n";
+
+        // -- Exec
+        let statements = split_statements(fx_sql);
+
+        // -- Check
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE TABLE a (id INT)",
+                "-- comment; not a statement\nINSERT INTO a VALUES (1)",
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_statements_strings_and_comments() -> Result<()> {
+        // -- Fixtures
+        let fx_sql = r#"
+            INSERT INTO a (t) VALUES ('it''s; fine');
+            INSERT INTO a (t) VALUES (E'back\'slash;');
+            SELECT "odd;name" FROM a /* block; /* nested; */ still */;
+            -- trailing comment only;
+        "#;
+
+        // -- Exec
+        let statements = split_statements(fx_sql);
+
+        // -- Check
+        assert_eq!(statements.len(), 3, "Statements: {statements:#?}");
+        assert_eq!(statements[0], "INSERT INTO a (t) VALUES ('it''s; fine')");
+        assert_eq!(statements[1], r#"INSERT INTO a (t) VALUES (E'back\'slash;')"#);
+        assert!(statements[2].ends_with("still */"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_statements_dollar_quoted() -> Result<()> {
+        // -- Fixtures
+        let fx_sql = r#"
+            CREATE FUNCTION f() RETURNS trigger AS $$
+            BEGIN
+                RAISE EXCEPTION 'no; way';
+            END;
+            $$ LANGUAGE plpgsql;
+            DO $body1$ BEGIN PERFORM 1; END $body1$;
+            PREPARE p AS SELECT $1::int;
+        "#;
+
+        // -- Exec
+        let statements = split_statements(fx_sql);
+
+        // -- Check
+        assert_eq!(statements.len(), 3, "Statements: {statements:#?}");
+        assert!(statements[0].ends_with("$$ LANGUAGE plpgsql"));
+        assert_eq!(statements[1], "DO $body1$ BEGIN PERFORM 1; END $body1$");
+        assert_eq!(statements[2], "PREPARE p AS SELECT $1::int");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -1,30 +1,54 @@
 use std::sync::{Arc, Mutex};
 use serde::{Deserialize, Serialize};
 use crate::ctx::Ctx;
+use crate::event::EventBus;
 use crate::model::store::{Db, new_db_pool};
 
 mod unit_test;
 mod error;
 mod base;
 mod store;
+pub mod audit;
+pub mod job;
+pub mod migration;
+pub mod rate_limit;
 pub mod ticket;
 pub mod task;
 pub mod user;
+pub mod webhook;
 
 pub use self::error::{Error, Result};
 
 #[derive(Clone)]
 pub struct DbContext {
     db: Db,
+    events: EventBus,
 }
 
 impl DbContext {
     pub async fn new() -> Result<Self> {
         let db = new_db_pool().await?;
-        Ok(DbContext { db })
+        let events = EventBus::new(&db).await?;
+        Ok(DbContext { db, events })
     }
 
     pub(in crate::model) fn db(&self) -> &Db {
         &self.db
     }
+
+    /// A round trip to the database, through the pool.
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.db).await?;
+
+        Ok(())
+    }
+
+    /// Open and idle connections of the pool.
+    pub fn pool_state(&self) -> (u32, usize) {
+        (self.db.size(), self.db.num_idle())
+    }
+
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
 }
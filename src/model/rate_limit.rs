@@ -0,0 +1,66 @@
+use std::time::Duration;
+use crate::model::{DbContext, Result};
+use crate::rate_limit::{Bucket, Decision, Quota};
+
+/// The buckets of the `postgres` rate limit store.
+pub struct RateLimitRepository;
+
+impl RateLimitRepository {
+    /// The row is locked while its token is taken, so the instances sharing
+    /// the database take from the same bucket. The elapsed time is measured
+    /// with the database clock, for the same reason.
+    pub async fn take(db_context: &DbContext, key: &str, quota: Quota) -> Result<Decision> {
+        let mut tx = db_context.db().begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO rate_limit_bucket (key, tokens) VALUES ($1, $2)
+            ON CONFLICT (key) DO NOTHING
+            "#,
+        )
+        .bind(key)
+        .bind(quota.burst as f64)
+        .execute(&mut *tx)
+        .await?;
+
+        let (tokens, elapsed_sec): (f64, f64) = sqlx::query_as(
+            r#"
+            SELECT tokens, GREATEST(EXTRACT(EPOCH FROM now() - mtime), 0)::float8
+            FROM rate_limit_bucket WHERE key = $1
+            FOR UPDATE
+            "#,
+        )
+        .bind(key)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let mut bucket = Bucket { tokens };
+        let decision = bucket.take(quota, Duration::from_secs_f64(elapsed_sec));
+
+        sqlx::query(
+            r#"
+            UPDATE rate_limit_bucket
+            SET tokens = $2, mtime = now(), full_at = now() + make_interval(secs => $3)
+            WHERE key = $1
+            "#,
+        )
+        .bind(key)
+        .bind(bucket.tokens)
+        .bind(bucket.until_full(quota).as_secs_f64())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(decision)
+    }
+
+    /// Deletes the full buckets, the same as the missing ones.
+    pub async fn prune(db_context: &DbContext) -> Result<u64> {
+        let res = sqlx::query("DELETE FROM rate_limit_bucket WHERE full_at <= now()")
+            .execute(db_context.db())
+            .await?;
+
+        Ok(res.rows_affected())
+    }
+}
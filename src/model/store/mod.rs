@@ -3,16 +3,88 @@ mod error;
 pub use error::{Error, Result};
 
 use crate::config::config;
-use sqlx::postgres::PgPoolOptions;
-use sqlx::{Pool, Postgres};
+use crate::metrics::{metrics, GaugeGuard};
+use crate::utils::time_utils::backoff_delay;
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgConnectOptions, PgConnection, PgPoolOptions};
+use sqlx::{ConnectOptions, Connection, Pool, Postgres};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use tracing::warn;
 
 pub type Db = Pool<Postgres>;
 
+const CONNECT_RETRY_BASE: Duration = Duration::from_millis(500);
+const CONNECT_RETRY_MAX: Duration = Duration::from_secs(5);
+
+/// Counted in the `db_pool_waiters` metric while waiting for the connection.
+pub async fn acquire(db: &Db) -> sqlx::Result<PoolConnection<Postgres>> {
+    let _waiting = GaugeGuard::new(&metrics().db_pool_waiters);
+
+    db.acquire().await
+}
+
+/// Waits for the database while it is not reachable (e.g. starting along
+/// the API), for up to `DB_CONNECT_TIMEOUT_SEC`.
 pub async fn new_db_pool() -> Result<Db> {
-    let max_connections = if cfg!(test) { 1 } else { 5 };
-    PgPoolOptions::new()
+    let config = config();
+
+    let mut connect_options = PgConnectOptions::from_str(&config.DB_URL)
+        .map_err(|ex| Error::FailToCreatePool(ex.to_string()))?;
+    if config.DB_STATEMENT_TIMEOUT_SEC > 0. {
+        let timeout_ms = (config.DB_STATEMENT_TIMEOUT_SEC * 1000.).round() as u64;
+        connect_options = connect_options.options([("statement_timeout", timeout_ms)]);
+    }
+
+    let max_connections = if cfg!(test) { 1 } else { config.DB_MAX_CONNECTIONS };
+    let idle_timeout = (config.DB_IDLE_TIMEOUT_SEC > 0.)
+        .then(|| Duration::from_secs_f64(config.DB_IDLE_TIMEOUT_SEC));
+    let pool_options = PgPoolOptions::new()
         .max_connections(max_connections)
-        .connect(&config().DB_URL)
+        .min_connections(config.DB_MIN_CONNECTIONS.min(max_connections))
+        .acquire_timeout(Duration::from_secs_f64(config.DB_ACQUIRE_TIMEOUT_SEC))
+        .idle_timeout(idle_timeout);
+
+    wait_reachable(&connect_options, config.DB_CONNECT_TIMEOUT_SEC)
+        .await
+        .map_err(|ex| Error::FailToCreatePool(ex.to_string()))?;
+
+    pool_options
+        .connect_with(connect_options)
         .await
         .map_err(|ex| Error::FailToCreatePool(ex.to_string()))
 }
+
+/// With a single connection, as the pool retries on its own until its
+/// acquire timeout, silently.
+async fn wait_reachable(connect_options: &PgConnectOptions, timeout_sec: f64) -> sqlx::Result<()> {
+    let deadline = Instant::now() + Duration::from_secs_f64(timeout_sec);
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+
+        match PgConnection::connect_with(connect_options).await {
+            Ok(conn) => return conn.close().await,
+            Err(ex) if is_unreachable(&ex) && Instant::now() < deadline => {
+                let delay = backoff_delay(CONNECT_RETRY_BASE, CONNECT_RETRY_MAX, attempt);
+                warn!("{:<12} - database not reachable, retry in {delay:?}: {ex}", "STORE");
+
+                tokio::time::sleep(delay).await;
+            }
+            Err(ex) => return Err(ex),
+        }
+    }
+}
+
+/// Whether the database may be up later, a wrong password or database name is not retried.
+fn is_unreachable(ex: &sqlx::Error) -> bool {
+    // SQLSTATE 57P03: cannot_connect_now, the server is starting up.
+    const PG_CANNOT_CONNECT_NOW: &str = "57P03";
+
+    match ex {
+        sqlx::Error::Io(_) => true,
+        sqlx::Error::Database(db_err) => db_err.code().as_deref() == Some(PG_CANNOT_CONNECT_NOW),
+        _ => false,
+    }
+}
@@ -1,13 +1,17 @@
 use crate::model::{base, DbContext};
 use crate::model::Result;
 use crate::ctx::Ctx;
+use crate::event::Event;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use modql::field::Fields;
 use sqlx::FromRow;
 use crate::model::base::Repository;
+use crate::utils::regex_utils::RE_NOT_BLANK;
+use validator::Validate;
 // region: -- Task Types
 
-#[derive(Debug, Clone, Fields, FromRow, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, Deserialize, JsonSchema)]
 pub struct Task {
     pub id: i64,
     pub title: String,
@@ -16,13 +20,15 @@ pub struct Task {
     // pub desc: String,
 }
 
-#[derive(Fields, Deserialize)]
+#[derive(Fields, Deserialize, JsonSchema, Validate)]
 pub struct TaskForCreate {
+    #[validate(length(min = 1, max = 256), regex(path = *RE_NOT_BLANK, code = "not_blank"))]
     pub title: String,
 }
 
-#[derive(Fields, Deserialize)]
+#[derive(Fields, Deserialize, JsonSchema, Validate)]
 pub struct TaskForUpdate {
+    #[validate(length(min = 1, max = 256), regex(path = *RE_NOT_BLANK, code = "not_blank"))]
     pub title: Option<String>,
 }
 
@@ -42,7 +48,12 @@ impl TaskRepository {
         db_context: &DbContext,
         task_c: TaskForCreate,
     ) -> Result<i64> {
-        base::create::<Self, _>(ctx, db_context, task_c).await
+        let id = base::create::<Self, _>(ctx, db_context, task_c).await?;
+
+        let task = Self::get(ctx, db_context, id).await?;
+        db_context.events().publish(Event::TaskCreated { actor_id: ctx.user_id(), task }).await;
+
+        Ok(id)
     }
 
     pub async fn list(
@@ -66,7 +77,12 @@ impl TaskRepository {
         id: i64,
         task_u: TaskForUpdate,
     ) -> Result<()> {
-        base::update::<Self, _>(ctx, db_context, id, task_u).await
+        base::update::<Self, _>(ctx, db_context, id, task_u).await?;
+
+        let task = Self::get(ctx, db_context, id).await?;
+        db_context.events().publish(Event::TaskUpdated { actor_id: ctx.user_id(), task }).await;
+
+        Ok(())
     }
 
     pub async fn delete(
@@ -74,7 +90,14 @@ impl TaskRepository {
         db_context: &DbContext,
         id: i64,
     ) -> Result<()> {
-        base::delete::<Self, Task>(ctx, db_context, id).await
+        // Read first, the event carries the deleted entity.
+        let task = Self::get(ctx, db_context, id).await?;
+
+        base::delete::<Self>(ctx, db_context, id).await?;
+
+        db_context.events().publish(Event::TaskDeleted { actor_id: ctx.user_id(), task }).await;
+
+        Ok(())
     }
 }
 
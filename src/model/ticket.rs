@@ -1,64 +1,146 @@
-use std::sync::{Arc, Mutex};
-
+use crate::ctx::Ctx;
+use crate::model::base::{self, Repository};
+use crate::model::DbContext;
+use crate::model::Result;
+use modql::field::Fields;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use crate::utils::regex_utils::RE_NOT_BLANK;
+use validator::Validate;
 
-use crate::ctx::Ctx;
-use crate::model::{base, DbContext};
-use crate::model::{Error, Result};
+// region: -- Ticket Types
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
 pub struct Ticket {
-    pub id: u64,
+    pub id: i64,
     pub cid: i64,
     pub title: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Validate)]
 pub struct TicketForCreate {
+    #[validate(length(min = 1, max = 256), regex(path = *RE_NOT_BLANK, code = "not_blank"))]
     pub title: String,
 }
 
-#[derive(Clone)]
-pub struct TicketRepository {
-    tickets_store: Arc<Mutex<Vec<Option<Ticket>>>>,
+#[derive(Fields)]
+struct TicketForInsert {
+    cid: i64,
+    title: String,
 }
 
-impl TicketRepository {
-    pub async fn new() -> Result<Self> {
-        Ok(Self {
-            tickets_store: Arc::default()
-        })
-    }
+#[derive(Fields, Deserialize, JsonSchema, Validate)]
+pub struct TicketForUpdate {
+    #[validate(length(min = 1, max = 256), regex(path = *RE_NOT_BLANK, code = "not_blank"))]
+    pub title: Option<String>,
+}
+
+// endregion: -- Ticket Types
 
-    pub async fn create_ticket(&self, ctx: Ctx, ticket_fc: TicketForCreate) -> Result<Ticket> {
-        let mut store = self.tickets_store.lock().unwrap();
+// region: -- TicketRepository
 
-        let id = store.len() as u64;
-        let ticket = Ticket {
-            id,
+pub struct TicketRepository;
+
+impl Repository for TicketRepository {
+    const TABLE: &'static str = "ticket";
+}
+
+impl TicketRepository {
+    pub async fn create(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        ticket_c: TicketForCreate,
+    ) -> Result<i64> {
+        let ticket_i = TicketForInsert {
             cid: ctx.user_id(),
-            title: ticket_fc.title,
+            title: ticket_c.title,
         };
-        store.push(Some(ticket.clone()));
 
-        Ok(ticket)
+        base::create::<Self, _>(ctx, db_context, ticket_i).await
     }
 
-    pub async fn list_tickets(&self, ctx: Ctx) -> Result<Vec<Ticket>> {
-        let store = self.tickets_store.lock().unwrap();
+    pub async fn list(
+        ctx: &Ctx,
+        db_context: &DbContext,
+    ) -> Result<Vec<Ticket>> {
+        base::list::<Self, _>(ctx, db_context).await
+    }
 
-        let tickets = store.iter()
-            .filter_map(|t| t.clone())
-            .collect();
+    pub async fn get(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+    ) -> Result<Ticket> {
+        base::get::<Self, _>(ctx, db_context, id).await
+    }
 
-        Ok(tickets)
+    pub async fn update(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+        ticket_u: TicketForUpdate,
+    ) -> Result<()> {
+        base::update::<Self, _>(ctx, db_context, id, ticket_u).await
     }
 
-    pub async fn delete_ticket(&self, ctx: Ctx, id: u64) -> Result<Ticket> {
-        let mut store = self.tickets_store.lock().unwrap();
+    pub async fn delete(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+    ) -> Result<()> {
+        base::delete::<Self>(ctx, db_context, id).await
+    }
+}
+
+// endregion: -- TicketRepository
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{_dev_utils, model};
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_create_ok_cid_from_ctx() -> Result<()> {
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let fx_title = "test_create_ok_cid_from_ctx title";
+
+        let id = TicketRepository::create(
+            &ctx,
+            &db_context,
+            TicketForCreate { title: fx_title.to_string() },
+        ).await?;
+
+        let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+        assert_eq!(ticket.title, fx_title);
+        assert_eq!(ticket.cid, 1000);
+
+        TicketRepository::delete(&ctx, &db_context, id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_delete_err_not_found() -> Result<()> {
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_id = 100;
+
+        let res = TicketRepository::delete(&ctx, &db_context, fx_id).await;
 
-        let ticket = store.get_mut(id as usize).and_then(|t| t.take());
+        assert!(
+            matches!(
+                res,
+                Err(model::Error::EntityNotFound { entity: "ticket", id: 100 })
+            ),
+            "EntityNotFound not matching"
+        );
 
-        ticket.ok_or(Error::TicketDeleteIdNotFound { id })
+        Ok(())
     }
 }
@@ -33,6 +33,25 @@ mod tests {
         Ok(())
     }
 
+    #[serial]
+    #[tokio::test]
+    pub async fn test_create_err_string_too_long() -> Result<()> {
+        let mm = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+
+        let task_c = TaskForCreate {
+            title: "a".repeat(300)
+        };
+        let res = TaskRepository::create(&ctx, &mm, task_c).await;
+
+        assert!(
+            matches!(res, Err(model::Error::StringTooLong { .. })),
+            "StringTooLong not matching, was `{res:?}`"
+        );
+
+        Ok(())
+    }
+
     #[serial]
     #[tokio::test]
     pub async fn test_get_err_not_found() -> Result<()> {
@@ -1,9 +1,10 @@
 use std::vec;
 
 use crate::ctx::Ctx;
+use crate::model::audit::{AuditAction, AuditEventForCreate, AuditRepository};
 use crate::model::base::{self, Repository};
-use crate::model::DbContext;
-use crate::model::Result;
+use crate::model::{store, DbContext};
+use crate::model::{Error, Result};
 use crate::pwd::{self, ContentToHash};
 use hmac::digest::typenum::Exp;
 use modql::field::{Field, Fields, HasFields};
@@ -12,8 +13,10 @@ use sea_query_binder::SqlxBinder;
 use serde::de::value;
 use serde::{Deserialize, Serialize};
 use sqlx::postgres::PgRow;
-use sqlx::FromRow;
+use sqlx::{Connection, FromRow, PgConnection};
 use uuid::Uuid;
+use validator::Validate;
+use crate::utils::regex_utils::RE_USERNAME;
 
 #[derive(Clone, Debug, FromRow, Fields, Serialize)]
 pub struct User {
@@ -21,9 +24,11 @@ pub struct User {
     pub username: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Validate)]
 pub struct UserForCreate {
+    #[validate(length(min = 3, max = 128), regex(path = *RE_USERNAME, code = "username_chars"))]
     pub username: String,
+    #[validate(length(min = 8, max = 256))]
     pub pwd_clear: String,
 }
 
@@ -40,6 +45,7 @@ pub struct UserForLogin {
     pub pwd: Option<String>,
     pub pwd_salt: Uuid,
     pub token_salt: Uuid,
+    pub disabled: bool,
 }
 
 #[derive(Clone, FromRow, Fields, Debug)]
@@ -48,6 +54,8 @@ pub struct UserForAuth {
     pub username: String,
 
     pub token_salt: Uuid,
+    pub disabled: bool,
+    pub is_admin: bool,
 }
 
 pub trait UserBy: HasFields + for<'r> FromRow<'r, PgRow> + Unpin + Send {}
@@ -61,6 +69,8 @@ pub enum UserIden {
     Id,
     Username,
     Pwd,
+    Disabled,
+    IsAdmin,
 }
 
 pub struct UserRepository;
@@ -70,6 +80,24 @@ impl Repository for UserRepository {
 }
 
 impl UserRepository {
+    /// Expects an already validated `user_c`. In one transaction, a failed
+    /// password hash leaves no user without a password.
+    pub async fn create(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        user_c: UserForCreate,
+    ) -> Result<i64> {
+        let UserForCreate { username, pwd_clear } = user_c;
+
+        let mut conn = store::acquire(db_context.db()).await?;
+        let mut tx = conn.begin().await?;
+        let id = base::create_in::<Self, _>(ctx, &mut tx, UserForInsert { username }).await?;
+        Self::update_pwd_in(ctx, &mut tx, id, &pwd_clear).await?;
+        tx.commit().await?;
+
+        Ok(id)
+    }
+
     pub async fn get<E>(ctx: &Ctx, db_context: &DbContext, id: i64) -> Result<E>
     where
         E: UserBy,
@@ -105,9 +133,30 @@ impl UserRepository {
         id: i64,
         pwd_clear: &str,
     ) -> Result<()> {
-        let db = db_context.db();
+        let mut tx = db_context.db().begin().await?;
+        Self::update_pwd_in(ctx, &mut tx, id, pwd_clear).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
 
-        let user: UserForLogin = Self::get(ctx, db_context, id).await?;
+    async fn update_pwd_in(
+        ctx: &Ctx,
+        conn: &mut PgConnection,
+        id: i64,
+        pwd_clear: &str,
+    ) -> Result<()> {
+        let mut query = Query::select();
+        query
+            .from(Self::table())
+            .columns(UserForLogin::field_idens())
+            .and_where(Expr::col(UserIden::Id).eq(id));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let user: UserForLogin = sqlx::query_as_with(&sql, values)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or(Error::EntityNotFound { entity: Self::TABLE, id })?;
 
         let pwd = pwd::hash_pwd(&ContentToHash {
             content: pwd_clear.to_string(),
@@ -121,11 +170,85 @@ impl UserRepository {
             .and_where(Expr::col(UserIden::Id).eq(id));
 
         let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
-        let _count = sqlx::query_with(&sql, values)
-            .execute(db)
+        sqlx::query_with(&sql, values).execute(&mut *conn).await?;
+
+        let event = AuditEventForCreate::new(ctx, AuditAction::PasswordChange).entity(Self::TABLE, id);
+        AuditRepository::record_in(conn, event).await?;
+
+        Ok(())
+    }
+
+    /// Disabling also revokes the tokens already issued, see `mw_ctx_resolver`.
+    pub async fn set_disabled(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+        disabled: bool,
+    ) -> Result<()> {
+        let action = if disabled { AuditAction::UserDisable } else { AuditAction::UserEnable };
+
+        Self::set_flag(ctx, db_context, id, UserIden::Disabled, disabled, action).await
+    }
+
+    /// A new token salt, the tokens already issued no longer validate.
+    pub async fn revoke_tokens(ctx: &Ctx, db_context: &DbContext, id: i64) -> Result<()> {
+        let mut tx = db_context.db().begin().await?;
+        let count = sqlx::query(r#"UPDATE "user" SET token_salt = gen_random_uuid() WHERE id = $1"#)
+            .bind(id)
+            .execute(&mut *tx)
             .await?
             .rows_affected();
-        
+
+        if count == 0 {
+            return Err(Error::EntityNotFound { entity: Self::TABLE, id });
+        }
+
+        let event = AuditEventForCreate::new(ctx, AuditAction::TokensRevoke).entity(Self::TABLE, id);
+        AuditRepository::record_in(&mut tx, event).await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    pub async fn set_admin(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+        is_admin: bool,
+    ) -> Result<()> {
+        let action = if is_admin { AuditAction::AdminGrant } else { AuditAction::AdminRevoke };
+
+        Self::set_flag(ctx, db_context, id, UserIden::IsAdmin, is_admin, action).await
+    }
+
+    async fn set_flag(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+        flag: UserIden,
+        value: bool,
+        action: AuditAction,
+    ) -> Result<()> {
+        let mut query = Query::update();
+        query
+            .table(Self::table())
+            .value(flag, value)
+            .and_where(Expr::col(UserIden::Id).eq(id));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let mut tx = db_context.db().begin().await?;
+        let count = sqlx::query_with(&sql, values)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+        if count == 0 {
+            return Err(Error::EntityNotFound { entity: Self::TABLE, id });
+        }
+
+        AuditRepository::record_in(&mut tx, AuditEventForCreate::new(ctx, action).entity(Self::TABLE, id)).await?;
+        tx.commit().await?;
+
         Ok(())
     }
 }
@@ -149,4 +272,54 @@ mod tests {
 
         Ok(())
     }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_insert_err_duplicate_username() -> Result<()> {
+        let db_context = crate::_dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_user_i = UserForInsert {
+            username: "demo1".to_string(),
+        };
+
+        let res = base::create::<UserRepository, _>(&ctx, &db_context, fx_user_i).await;
+
+        assert!(
+            matches!(
+                &res,
+                Err(crate::model::Error::UniqueViolation { constraint: Some(c), .. })
+                    if c == "user_username_key"
+            ),
+            "UniqueViolation not matching, was `{res:?}`"
+        );
+
+        Ok(())
+    }
+
+    #[serial_test::serial]
+    #[tokio::test]
+    async fn test_create_ok_then_disable() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = crate::_dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_user_c = UserForCreate {
+            username: "test_create_ok_then_disable".to_string(),
+            pwd_clear: "welcome-01".to_string(),
+        };
+
+        // -- Exec
+        let id = UserRepository::create(&ctx, &db_context, fx_user_c).await?;
+        UserRepository::set_disabled(&ctx, &db_context, id, true).await?;
+
+        // -- Check
+        let user: UserForLogin = UserRepository::get(&ctx, &db_context, id).await?;
+        assert!(user.disabled);
+        let pwd = user.pwd.context("Should have a password")?;
+        pwd::validate_pwd(
+            &ContentToHash { salt: user.pwd_salt, content: "welcome-01".to_string() },
+            &pwd,
+        )?;
+
+        Ok(())
+    }
 }
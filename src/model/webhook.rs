@@ -0,0 +1,681 @@
+use crate::ctx::Ctx;
+use crate::event::{EventEnvelope, EVENT_TYPES};
+use crate::model::base::{self, CommonIden, Repository};
+use crate::model::{DbContext, Error, Result};
+use modql::field::{Fields, HasFields};
+use schemars::JsonSchema;
+use sea_query::{Expr, Iden, IntoIden, OnConflict, PostgresQueryBuilder, Query, UpdateStatement};
+use sea_query_binder::SqlxBinder;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use time::OffsetDateTime;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+// region: -- Webhook Types
+
+/// The secret is write-only, it never leaves the service once registered.
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct Webhook {
+    pub id: i64,
+    pub cid: i64,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub enabled: bool,
+    pub failure_count: i32,
+}
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct WebhookForCreate {
+    #[validate(url, length(max = 2048), custom(function = "validate_webhook_url"))]
+    pub url: String,
+    #[validate(length(min = 16, max = 256))]
+    pub secret: String,
+    #[validate(length(min = 1), custom(function = "validate_event_types"))]
+    pub event_types: Vec<String>,
+}
+
+#[derive(Fields)]
+struct WebhookForInsert {
+    cid: i64,
+    url: String,
+    secret: String,
+    event_types: Vec<String>,
+}
+
+#[derive(Fields, Deserialize, JsonSchema, Validate)]
+pub struct WebhookForUpdate {
+    #[validate(url, length(max = 2048), custom(function = "validate_webhook_url"))]
+    pub url: Option<String>,
+    #[validate(length(min = 16, max = 256))]
+    pub secret: Option<String>,
+    #[validate(length(min = 1), custom(function = "validate_event_types"))]
+    pub event_types: Option<Vec<String>>,
+    /// Re-enabling a webhook also resets its failure count.
+    pub enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct WebhookForDispatch {
+    pub id: i64,
+    pub cid: i64,
+}
+
+/// The target address is checked again on each delivery, see `webhook::deliver`.
+fn validate_webhook_url(url: &str) -> core::result::Result<(), ValidationError> {
+    match reqwest::Url::parse(url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => Ok(()),
+        _ => Err(ValidationError::new("unsupported_scheme")),
+    }
+}
+
+fn validate_event_types(event_types: &[String]) -> core::result::Result<(), ValidationError> {
+    if event_types.iter().all(|t| EVENT_TYPES.contains(&t.as_str())) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("unknown_event_type"))
+    }
+}
+
+#[derive(Iden)]
+enum WebhookIden {
+    Cid,
+    Enabled,
+    EventTypes,
+    FailureCount,
+}
+
+// endregion: -- Webhook Types
+
+// region: -- WebhookDelivery Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryStatus {
+    Pending,
+    Delivered,
+    Failed,
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Delivered => "delivered",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Fields, FromRow, Serialize, JsonSchema)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub status: String,
+    pub attempts: i32,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub next_attempt_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    #[schemars(with = "String")]
+    pub ctime: OffsetDateTime,
+}
+
+#[derive(Fields)]
+struct WebhookDeliveryForInsert {
+    webhook_id: i64,
+    event_id: Uuid,
+    event_type: String,
+    payload: Value,
+}
+
+/// A claimed delivery, with what is needed to send and sign it.
+#[derive(Debug, Clone, FromRow)]
+pub struct WebhookDeliveryForSend {
+    pub id: i64,
+    pub webhook_id: i64,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub attempts: i32,
+    pub url: String,
+    pub secret: String,
+    /// The `next_attempt_at` set by the claim, the reports of the attempt
+    /// only apply while it is unchanged.
+    pub leased_until: OffsetDateTime,
+}
+
+#[derive(Iden)]
+enum WebhookDeliveryIden {
+    Id,
+    WebhookId,
+    EventId,
+    Status,
+    Attempts,
+    NextAttemptAt,
+    LastStatusCode,
+    LastError,
+}
+
+// endregion: -- WebhookDelivery Types
+
+// region: -- WebhookRepository
+
+pub struct WebhookRepository;
+
+impl Repository for WebhookRepository {
+    const TABLE: &'static str = "webhook";
+}
+
+impl WebhookRepository {
+    pub async fn create(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        webhook_c: WebhookForCreate,
+    ) -> Result<i64> {
+        let webhook_i = WebhookForInsert {
+            cid: ctx.user_id(),
+            url: webhook_c.url,
+            secret: webhook_c.secret,
+            event_types: webhook_c.event_types,
+        };
+
+        base::create::<Self, _>(ctx, db_context, webhook_i).await
+    }
+
+    /// Only the webhooks registered by the `Ctx` user.
+    pub async fn list(
+        ctx: &Ctx,
+        db_context: &DbContext,
+    ) -> Result<Vec<Webhook>> {
+        let db = db_context.db();
+
+        let mut query = Query::select();
+        query
+            .from(Self::table())
+            .columns(Webhook::field_column_refs())
+            .and_where(Expr::col(WebhookIden::Cid).eq(ctx.user_id()))
+            .order_by(CommonIden::Id, sea_query::Order::Asc);
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let webhooks = sqlx::query_as_with::<_, Webhook, _>(&sql, values)
+            .fetch_all(db)
+            .await?;
+
+        Ok(webhooks)
+    }
+
+    /// Webhooks of other users are reported as not found.
+    pub async fn get(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+    ) -> Result<Webhook> {
+        let webhook: Webhook = base::get::<Self, _>(ctx, db_context, id).await?;
+
+        if webhook.cid != ctx.user_id() {
+            return Err(Error::EntityNotFound { entity: Self::TABLE, id });
+        }
+
+        Ok(webhook)
+    }
+
+    pub async fn update(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+        webhook_u: WebhookForUpdate,
+    ) -> Result<()> {
+        Self::get(ctx, db_context, id).await?;
+
+        let reset_failures = webhook_u.enabled == Some(true);
+
+        base::update::<Self, _>(ctx, db_context, id, webhook_u).await?;
+
+        if reset_failures {
+            Self::record_success(ctx, db_context, id).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn delete(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+    ) -> Result<()> {
+        Self::get(ctx, db_context, id).await?;
+
+        base::delete::<Self>(ctx, db_context, id).await
+    }
+
+    /// Enabled webhooks of every user, the caller checks the event visibility.
+    pub async fn list_for_event_type(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        event_type: &str,
+    ) -> Result<Vec<WebhookForDispatch>> {
+        let db = db_context.db();
+
+        let mut query = Query::select();
+        query
+            .from(Self::table())
+            .columns([CommonIden::Id.into_iden(), WebhookIden::Cid.into_iden()])
+            .and_where(Expr::col(WebhookIden::Enabled).eq(true))
+            .and_where(Expr::cust_with_values("$1 = ANY(event_types)", [event_type]));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let webhooks = sqlx::query_as_with::<_, WebhookForDispatch, _>(&sql, values)
+            .fetch_all(db)
+            .await?;
+
+        Ok(webhooks)
+    }
+
+    pub async fn record_success(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+    ) -> Result<()> {
+        let db = db_context.db();
+
+        let mut query = Query::update();
+        query
+            .table(Self::table())
+            .value(WebhookIden::FailureCount, 0)
+            .and_where(Expr::col(CommonIden::Id).eq(id));
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values).execute(db).await?;
+
+        Ok(())
+    }
+
+    /// Returns `true` when this failure disabled the webhook. A webhook
+    /// disabled by its owner stays disabled.
+    pub async fn record_failure(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        id: i64,
+        disable_after: i32,
+    ) -> Result<bool> {
+        let db = db_context.db();
+
+        // `RETURNING` gives the new row, the old `enabled` comes from the locked select.
+        let disabled: Option<(bool,)> = sqlx::query_as(
+            r#"
+            UPDATE webhook w
+            SET failure_count = w.failure_count + 1,
+                enabled = w.enabled AND w.failure_count + 1 < $2
+            FROM (SELECT id, enabled FROM webhook WHERE id = $1 FOR UPDATE) old
+            WHERE w.id = old.id
+            RETURNING old.enabled AND NOT w.enabled
+            "#,
+        )
+        .bind(id)
+        .bind(disable_after)
+        .fetch_optional(db)
+        .await?;
+
+        Ok(matches!(disabled, Some((true,))))
+    }
+}
+
+// endregion: -- WebhookRepository
+
+// region: -- WebhookDeliveryRepository
+
+pub struct WebhookDeliveryRepository;
+
+impl Repository for WebhookDeliveryRepository {
+    const TABLE: &'static str = "webhook_delivery";
+}
+
+impl WebhookDeliveryRepository {
+    /// Idempotent per `(webhook, event)`, so instances sharing a Postgres
+    /// event bus can all enqueue the same event.
+    pub async fn enqueue(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        webhook_id: i64,
+        envelope: &EventEnvelope,
+    ) -> Result<()> {
+        let db = db_context.db();
+
+        let delivery_i = WebhookDeliveryForInsert {
+            webhook_id,
+            event_id: envelope.id,
+            event_type: envelope.event.name().to_string(),
+            payload: serde_json::to_value(envelope).unwrap_or_default(),
+        };
+        let (columns, sea_values) = delivery_i.not_none_fields().for_sea_insert();
+
+        let mut query = Query::insert();
+        query
+            .into_table(Self::table())
+            .columns(columns)
+            .values(sea_values)?
+            .on_conflict(
+                OnConflict::columns([WebhookDeliveryIden::WebhookId, WebhookDeliveryIden::EventId])
+                    .do_nothing()
+                    .to_owned(),
+            );
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        sqlx::query_with(&sql, values).execute(db).await?;
+
+        Ok(())
+    }
+
+    /// The delivery log of one of the `Ctx` user's webhooks, most recent first.
+    pub async fn list_for_webhook(
+        ctx: &Ctx,
+        db_context: &DbContext,
+        webhook_id: i64,
+    ) -> Result<Vec<WebhookDelivery>> {
+        WebhookRepository::get(ctx, db_context, webhook_id).await?;
+
+        let db = db_context.db();
+
+        let mut query = Query::select();
+        query
+            .from(Self::table())
+            .columns(WebhookDelivery::field_column_refs())
+            .and_where(Expr::col(WebhookDeliveryIden::WebhookId).eq(webhook_id))
+            .order_by(WebhookDeliveryIden::Id, sea_query::Order::Desc);
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let deliveries = sqlx::query_as_with::<_, WebhookDelivery, _>(&sql, values)
+            .fetch_all(db)
+            .await?;
+
+        Ok(deliveries)
+    }
+
+    /// Deletes the delivered and failed deliveries created more than
+    /// `keep_days` ago.
+    pub async fn purge_finished(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        keep_days: i32,
+    ) -> Result<u64> {
+        let res = sqlx::query(
+            r#"
+            DELETE FROM webhook_delivery
+            WHERE status IN ('delivered', 'failed') AND ctime < now() - make_interval(days => $1)
+            "#,
+        )
+        .bind(keep_days)
+        .execute(db_context.db())
+        .await?;
+
+        Ok(res.rows_affected())
+    }
+
+    /// Claims up to `limit` due deliveries of enabled webhooks. The claim is a
+    /// lease: `next_attempt_at` is pushed by `lease_sec` so another worker
+    /// only picks the delivery again if this one never reports back.
+    pub async fn claim_due(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        limit: i64,
+        lease_sec: f64,
+    ) -> Result<Vec<WebhookDeliveryForSend>> {
+        let db = db_context.db();
+
+        let deliveries = sqlx::query_as::<_, WebhookDeliveryForSend>(
+            r#"
+            WITH due AS (
+                SELECT d.id
+                FROM webhook_delivery d
+                JOIN webhook w ON w.id = d.webhook_id
+                WHERE d.status = 'pending' AND d.next_attempt_at <= now() AND w.enabled
+                ORDER BY d.next_attempt_at
+                LIMIT $1
+                FOR UPDATE OF d SKIP LOCKED
+            )
+            UPDATE webhook_delivery d
+            SET next_attempt_at = now() + make_interval(secs => $2)
+            FROM due, webhook w
+            WHERE d.id = due.id AND w.id = d.webhook_id
+            RETURNING d.id, d.webhook_id, d.event_id, d.event_type, d.payload, d.attempts, w.url, w.secret,
+                d.next_attempt_at AS leased_until
+            "#,
+        )
+        .bind(limit)
+        .bind(lease_sec)
+        .fetch_all(db)
+        .await?;
+
+        Ok(deliveries)
+    }
+
+    /// `LeaseLost` once the claim expired, see `claim_due`.
+    pub async fn mark_delivered(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        delivery: &WebhookDeliveryForSend,
+        status_code: u16,
+    ) -> Result<()> {
+        let db = db_context.db();
+
+        let mut query = Query::update();
+        query
+            .table(Self::table())
+            .value(WebhookDeliveryIden::Status, DeliveryStatus::Delivered.as_str())
+            .value(WebhookDeliveryIden::Attempts, Expr::col(WebhookDeliveryIden::Attempts).add(1))
+            .value(WebhookDeliveryIden::LastStatusCode, status_code as i32)
+            .value(WebhookDeliveryIden::LastError, Option::<String>::None);
+        Self::and_where_leased(&mut query, delivery);
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let count = sqlx::query_with(&sql, values).execute(db).await?.rows_affected();
+
+        Self::check_leased(count, delivery)
+    }
+
+    /// `retry_in_sec` of `None` gives up on the delivery. `LeaseLost` once
+    /// the claim expired.
+    pub async fn mark_attempt_failed(
+        _ctx: &Ctx,
+        db_context: &DbContext,
+        delivery: &WebhookDeliveryForSend,
+        status_code: Option<u16>,
+        error: String,
+        retry_in_sec: Option<f64>,
+    ) -> Result<()> {
+        let db = db_context.db();
+
+        let status = match retry_in_sec {
+            Some(_) => DeliveryStatus::Pending,
+            None => DeliveryStatus::Failed,
+        };
+
+        let mut query = Query::update();
+        query
+            .table(Self::table())
+            .value(WebhookDeliveryIden::Status, status.as_str())
+            .value(WebhookDeliveryIden::Attempts, Expr::col(WebhookDeliveryIden::Attempts).add(1))
+            .value(WebhookDeliveryIden::LastStatusCode, status_code.map(i32::from))
+            .value(WebhookDeliveryIden::LastError, error);
+        if let Some(retry_in_sec) = retry_in_sec {
+            query.value(
+                WebhookDeliveryIden::NextAttemptAt,
+                Expr::cust_with_values("now() + make_interval(secs => $1)", [retry_in_sec]),
+            );
+        }
+        Self::and_where_leased(&mut query, delivery);
+
+        let (sql, values) = query.build_sqlx(PostgresQueryBuilder);
+        let count = sqlx::query_with(&sql, values).execute(db).await?.rows_affected();
+
+        Self::check_leased(count, delivery)
+    }
+
+    /// A claim of another worker pushed `next_attempt_at` again.
+    fn and_where_leased(query: &mut UpdateStatement, delivery: &WebhookDeliveryForSend) {
+        query
+            .and_where(Expr::col(WebhookDeliveryIden::Id).eq(delivery.id))
+            .and_where(Expr::col(WebhookDeliveryIden::Status).eq(DeliveryStatus::Pending.as_str()))
+            .and_where(Expr::col(WebhookDeliveryIden::NextAttemptAt).eq(delivery.leased_until));
+    }
+
+    fn check_leased(count: u64, delivery: &WebhookDeliveryForSend) -> Result<()> {
+        if count == 0 {
+            return Err(Error::LeaseLost { entity: Self::TABLE, id: delivery.id });
+        }
+
+        Ok(())
+    }
+}
+
+// endregion: -- WebhookDeliveryRepository
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_get_err_other_user() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx_owner = Ctx::new(1000)?;
+        let ctx_other = Ctx::new(1001)?;
+        let id = WebhookRepository::create(
+            &ctx_owner,
+            &db_context,
+            WebhookForCreate {
+                url: "http://localhost:9999/hook".to_string(),
+                secret: "test_get_err_other_user secret".to_string(),
+                event_types: vec!["task.created".to_string()],
+            },
+        ).await?;
+
+        // -- Exec
+        let res = WebhookRepository::get(&ctx_other, &db_context, id).await;
+        let listed = WebhookRepository::list(&ctx_other, &db_context).await?;
+
+        // -- Check
+        assert!(
+            matches!(res, Err(Error::EntityNotFound { entity: "webhook", .. })),
+            "Should not see another user's webhook"
+        );
+        assert!(listed.iter().all(|w| w.id != id));
+
+        // -- Clean
+        WebhookRepository::delete(&ctx_owner, &db_context, id).await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_url_scheme() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_webhook = |url: &str| WebhookForCreate {
+            url: url.to_string(),
+            secret: "test_validate_url_scheme secret".to_string(),
+            event_types: vec!["task.created".to_string()],
+        };
+
+        // -- Exec & Check
+        assert!(fx_webhook("https://example.com/hook").validate().is_ok());
+        assert!(fx_webhook("http://example.com/hook").validate().is_ok());
+        assert!(fx_webhook("ftp://example.com/hook").validate().is_err());
+        assert!(fx_webhook("file:///etc/passwd").validate().is_err());
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_record_failure_keeps_disabled() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let id = WebhookRepository::create(
+            &ctx,
+            &db_context,
+            WebhookForCreate {
+                url: "http://localhost:9999/hook".to_string(),
+                secret: "test_record_failure_keeps_disabled secret".to_string(),
+                event_types: vec!["task.created".to_string()],
+            },
+        ).await?;
+        WebhookRepository::update(
+            &ctx,
+            &db_context,
+            id,
+            WebhookForUpdate { url: None, secret: None, event_types: None, enabled: Some(false) },
+        ).await?;
+
+        // -- Exec
+        // e.g. a delivery in flight when the owner disabled the webhook.
+        let disabled = WebhookRepository::record_failure(&ctx, &db_context, id, 20).await?;
+
+        // -- Check
+        assert!(!disabled, "Should not report a webhook already disabled");
+        let webhook = WebhookRepository::get(&ctx, &db_context, id).await?;
+        assert!(!webhook.enabled, "Should stay disabled");
+        assert_eq!(webhook.failure_count, 1);
+
+        // -- Clean
+        WebhookRepository::delete(&ctx, &db_context, id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_mark_delivered_err_lease_lost() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let id = WebhookRepository::create(
+            &ctx,
+            &db_context,
+            WebhookForCreate {
+                url: "http://localhost:9999/hook".to_string(),
+                secret: "test_mark_delivered_err_lease_lost secret".to_string(),
+                event_types: vec!["task.created".to_string()],
+            },
+        ).await?;
+        let fx_envelope = EventEnvelope {
+            id: Uuid::new_v4(),
+            event: crate::event::Event::TaskCreated {
+                actor_id: 1000,
+                task: crate::model::task::Task { id: 1000, title: "test_mark_delivered_err_lease_lost".to_string() },
+            },
+        };
+        WebhookDeliveryRepository::enqueue(&ctx, &db_context, id, &fx_envelope).await?;
+
+        // -- Exec
+        // The first lease expires at once, the delivery is claimed again by another worker.
+        let first = WebhookDeliveryRepository::claim_due(&ctx, &db_context, 1, 0.).await?;
+        let second = WebhookDeliveryRepository::claim_due(&ctx, &db_context, 1, 60.).await?;
+        let res_first = WebhookDeliveryRepository::mark_delivered(&ctx, &db_context, &first[0], 200).await;
+        let res_second = WebhookDeliveryRepository::mark_delivered(&ctx, &db_context, &second[0], 200).await;
+
+        // -- Check
+        assert_eq!(first[0].id, second[0].id);
+        assert!(
+            matches!(res_first, Err(Error::LeaseLost { entity: "webhook_delivery", .. })),
+            "Should have lost the lease, was `{res_first:?}`"
+        );
+        assert!(res_second.is_ok(), "Should hold the lease, was `{res_second:?}`");
+
+        // -- Clean
+        WebhookRepository::delete(&ctx, &db_context, id).await?;
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
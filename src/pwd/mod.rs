@@ -3,7 +3,7 @@ mod hmac_hasher;
 
 pub use self::error::{Error, Result};
 
-use crate::pwd::hmac_hasher::hmac_sha512_hash;
+pub(crate) use crate::pwd::hmac_hasher::hmac_sha512_hash;
 use uuid::Uuid;
 use crate::config::config;
 
@@ -0,0 +1,22 @@
+use std::fmt::Formatter;
+use derive_more::From;
+use crate::model;
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, From)]
+pub enum Error {
+    UnknownStoreBackend(String),
+    InvalidQuota(String),
+
+    #[from]
+    Model(model::Error),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
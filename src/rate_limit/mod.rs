@@ -0,0 +1,197 @@
+mod error;
+mod store;
+
+pub use self::error::{Error, Result};
+pub use self::store::{MemoryStore, PostgresStore, RateLimitStore};
+
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tracing::warn;
+use crate::config::Config;
+use crate::model::DbContext;
+
+// region: -- Limiter
+
+static LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateLimitBackend {
+    /// In-process only, each instance has its own buckets.
+    Memory,
+    /// Buckets in the `rate_limit_bucket` table, shared by the instances.
+    Postgres,
+}
+
+impl FromStr for RateLimitBackend {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "memory" => Ok(RateLimitBackend::Memory),
+            "postgres" => Ok(RateLimitBackend::Postgres),
+            other => Err(Error::UnknownStoreBackend(other.to_string())),
+        }
+    }
+}
+
+/// Sets the store of `limiter()`, once. Until then, the buckets are in memory.
+pub fn init(config: &Config, db_context: &DbContext) {
+    let store: Box<dyn RateLimitStore> = match config.RATE_LIMIT_STORE {
+        RateLimitBackend::Memory => Box::new(MemoryStore::default()),
+        RateLimitBackend::Postgres => Box::new(PostgresStore::new(db_context.clone())),
+    };
+    let _ = LIMITER.set(RateLimiter::new(store));
+}
+
+pub fn limiter() -> &'static RateLimiter {
+    LIMITER.get_or_init(|| RateLimiter::new(Box::new(MemoryStore::default())))
+}
+
+pub struct RateLimiter {
+    store: Box<dyn RateLimitStore>,
+}
+
+impl RateLimiter {
+    pub fn new(store: Box<dyn RateLimitStore>) -> Self {
+        Self { store }
+    }
+
+    /// Takes a token of the `key` bucket. A failing store is reported and the
+    /// request let through, rather than refusing every request.
+    pub async fn check(&self, key: &str, quota: Quota) -> Decision {
+        match self.store.take(key, quota).await {
+            Ok(decision) => decision,
+            Err(ex) => {
+                warn!("{:<12} - fail to take a token - {ex}", "RATE_LIMIT");
+                Decision::Allowed
+            }
+        }
+    }
+}
+
+// endregion: -- Limiter
+
+// region: -- Token Bucket
+
+/// `burst` requests at once, then refilled at `burst` per `period`,
+/// e.g. `10/min`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quota {
+    pub burst: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    pub fn new(burst: u32, period: Duration) -> Self {
+        Self { burst, period }
+    }
+
+    pub fn refill_per_sec(&self) -> f64 {
+        self.burst as f64 / self.period.as_secs_f64()
+    }
+}
+
+/// `<burst>/<sec|min|hour>`.
+impl FromStr for Quota {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidQuota(s.to_string());
+
+        let (burst, period) = s.trim().split_once('/').ok_or_else(invalid)?;
+        let burst: u32 = burst.trim().parse().map_err(|_| invalid())?;
+        let period = match period.trim() {
+            "sec" => Duration::from_secs(1),
+            "min" => Duration::from_secs(60),
+            "hour" => Duration::from_secs(3600),
+            _ => return Err(invalid()),
+        };
+        if burst == 0 {
+            return Err(invalid());
+        }
+
+        Ok(Quota::new(burst, period))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    Allowed,
+    Limited { retry_after: Duration },
+}
+
+/// The tokens left, the stores keep them with the time of the last take.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bucket {
+    pub tokens: f64,
+}
+
+impl Bucket {
+    pub fn full(quota: Quota) -> Self {
+        Self { tokens: quota.burst as f64 }
+    }
+
+    /// Refills for the time `elapsed` since the last take, then takes a token
+    /// when there is one.
+    pub fn take(&mut self, quota: Quota, elapsed: Duration) -> Decision {
+        let refill_per_sec = quota.refill_per_sec();
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * refill_per_sec).min(quota.burst as f64);
+
+        if self.tokens >= 1. {
+            self.tokens -= 1.;
+            Decision::Allowed
+        } else {
+            let retry_after = Duration::from_secs_f64((1. - self.tokens) / refill_per_sec);
+            Decision::Limited { retry_after }
+        }
+    }
+
+    /// Once full, the bucket is the same as a new one and can be dropped.
+    pub fn until_full(&self, quota: Quota) -> Duration {
+        Duration::from_secs_f64((quota.burst as f64 - self.tokens).max(0.) / quota.refill_per_sec())
+    }
+}
+
+// endregion: -- Token Bucket
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_quota_from_str() -> Result<()> {
+        // -- Exec & Check
+        assert_eq!("10/min".parse::<Quota>()?, Quota::new(10, Duration::from_secs(60)));
+        assert_eq!(" 3 / sec ".parse::<Quota>()?, Quota::new(3, Duration::from_secs(1)));
+        for fx_invalid in ["0/min", "10", "10/day", "-1/sec", "ten/min"] {
+            assert!(fx_invalid.parse::<Quota>().is_err(), "for {fx_invalid:?}");
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bucket_take_and_refill() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_quota: Quota = "2/min".parse()?;
+        let mut bucket = Bucket::full(fx_quota);
+
+        // -- Exec & Check
+        assert_eq!(bucket.take(fx_quota, Duration::ZERO), Decision::Allowed);
+        assert_eq!(bucket.take(fx_quota, Duration::ZERO), Decision::Allowed);
+        let Decision::Limited { retry_after } = bucket.take(fx_quota, Duration::from_secs(10)) else {
+            panic!("Should be limited once the burst is taken");
+        };
+        assert_eq!(retry_after.as_secs_f64().round(), 20., "Should wait for the rest of a token");
+        assert_eq!(bucket.take(fx_quota, Duration::from_secs(21)), Decision::Allowed);
+        assert_eq!(bucket.until_full(fx_quota).as_secs_f64().round(), 59.);
+        bucket.take(fx_quota, Duration::from_secs(3600));
+        assert_eq!(bucket.tokens, 1., "Should be capped to the burst");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
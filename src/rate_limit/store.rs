@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+use async_trait::async_trait;
+use tracing::warn;
+use crate::model::rate_limit::RateLimitRepository;
+use crate::model::DbContext;
+use crate::rate_limit::{Bucket, Decision, Quota, Result};
+
+/// Over it, the memory store drops its full buckets.
+const MEMORY_PRUNE_LEN: usize = 10_000;
+/// The Postgres store deletes the full buckets every so many takes.
+const PG_PRUNE_EVERY: u64 = 1_000;
+
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Takes a token of the `key` bucket, a new bucket being full.
+    async fn take(&self, key: &str, quota: Quota) -> Result<Decision>;
+}
+
+// region: -- MemoryStore
+
+#[derive(Default)]
+pub struct MemoryStore {
+    buckets: Mutex<HashMap<String, MemoryBucket>>,
+}
+
+struct MemoryBucket {
+    bucket: Bucket,
+    mtime: Instant,
+    full_at: Instant,
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryStore {
+    async fn take(&self, key: &str, quota: Quota) -> Result<Decision> {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().expect("rate limit buckets lock poisoned");
+
+        if buckets.len() > MEMORY_PRUNE_LEN {
+            buckets.retain(|_, entry| entry.full_at > now);
+        }
+
+        let entry = buckets.entry(key.to_string()).or_insert_with(|| MemoryBucket {
+            bucket: Bucket::full(quota),
+            mtime: now,
+            full_at: now,
+        });
+        let decision = entry.bucket.take(quota, now - entry.mtime);
+        entry.mtime = now;
+        entry.full_at = now + entry.bucket.until_full(quota);
+
+        Ok(decision)
+    }
+}
+
+// endregion: -- MemoryStore
+
+// region: -- PostgresStore
+
+pub struct PostgresStore {
+    db_context: DbContext,
+    takes: AtomicU64,
+}
+
+impl PostgresStore {
+    pub fn new(db_context: DbContext) -> Self {
+        Self { db_context, takes: AtomicU64::new(0) }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for PostgresStore {
+    async fn take(&self, key: &str, quota: Quota) -> Result<Decision> {
+        if self.takes.fetch_add(1, Ordering::Relaxed) % PG_PRUNE_EVERY == PG_PRUNE_EVERY - 1 {
+            if let Err(ex) = RateLimitRepository::prune(&self.db_context).await {
+                warn!("{:<12} - fail to prune the buckets - {ex}", "RATE_LIMIT");
+            }
+        }
+
+        Ok(RateLimitRepository::take(&self.db_context, key, quota).await?)
+    }
+}
+
+// endregion: -- PostgresStore
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[tokio::test]
+    async fn test_memory_store_buckets_by_key() -> Result<()> {
+        // -- Setup & Fixtures
+        let store = MemoryStore::default();
+        let fx_quota: Quota = "1/hour".parse()?;
+
+        // -- Exec
+        let first = store.take("fx-key-1", fx_quota).await?;
+        let second = store.take("fx-key-1", fx_quota).await?;
+        let other_key = store.take("fx-key-2", fx_quota).await?;
+
+        // -- Check
+        assert_eq!(first, Decision::Allowed);
+        assert!(matches!(second, Decision::Limited { retry_after } if retry_after.as_secs() > 3500));
+        assert_eq!(other_key, Decision::Allowed);
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_postgres_store_shared_by_instances() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let store_1 = PostgresStore::new(db_context.clone());
+        let store_2 = PostgresStore::new(db_context.clone());
+        let fx_quota: Quota = "2/hour".parse()?;
+        let fx_key = "test_postgres_store_shared_by_instances";
+
+        // -- Exec
+        let first = store_1.take(fx_key, fx_quota).await?;
+        let second = store_2.take(fx_key, fx_quota).await?;
+        let third = store_1.take(fx_key, fx_quota).await?;
+        RateLimitRepository::prune(&db_context).await?;
+
+        // -- Check
+        assert_eq!(first, Decision::Allowed);
+        assert_eq!(second, Decision::Allowed, "Should share the bucket of the first instance");
+        assert!(matches!(third, Decision::Limited { .. }));
+        assert!(
+            matches!(store_2.take(fx_key, fx_quota).await?, Decision::Limited { .. }),
+            "Should not prune a bucket which is not full"
+        );
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,162 @@
+mod error;
+
+pub use self::error::{Error, Result};
+
+use axum::http::HeaderMap;
+use opentelemetry::propagation::{Extractor, TextMapPropagator};
+use opentelemetry::trace::TracerProvider;
+use opentelemetry::Context;
+use opentelemetry_otlp::{Protocol, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing::warn;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+/// Keeps the exporter of the spans, if any, until the process exits.
+pub struct Telemetry {
+    provider: Option<SdkTracerProvider>,
+}
+
+/// Installs the global subscriber: the log lines, and the spans exported to
+/// `otel_endpoint` (the OTLP/HTTP base url, e.g. `http://localhost:4318`) when set.
+pub fn init(otel_endpoint: Option<&str>, service_name: &str) -> Result<Telemetry> {
+    let provider = otel_endpoint
+        .map(|endpoint| new_tracer_provider(endpoint, service_name))
+        .transpose()?;
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer("webapi")));
+
+    tracing_subscriber::registry()
+        .with(EnvFilter::from_default_env())
+        .with(tracing_subscriber::fmt::layer().without_time().with_target(false))
+        .with(otel_layer)
+        .init();
+
+    Ok(Telemetry { provider })
+}
+
+impl Telemetry {
+    /// Exports the spans still batched.
+    pub fn shutdown(self) {
+        if let Some(provider) = self.provider {
+            if let Err(ex) = provider.shutdown() {
+                warn!("{:<12} - telemetry shutdown - {ex}", "TELEMETRY");
+            }
+        }
+    }
+}
+
+fn new_tracer_provider(endpoint: &str, service_name: &str) -> Result<SdkTracerProvider> {
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_protocol(Protocol::HttpJson)
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build()?;
+
+    Ok(SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(Resource::builder().with_service_name(service_name.to_string()).build())
+        .build())
+}
+
+/// The remote parent from the W3C `traceparent` and `tracestate` headers,
+/// an empty context when missing or invalid.
+pub fn extract_context(headers: &HeaderMap) -> Context {
+    TraceContextPropagator::new().extract(&HeaderExtractor(headers))
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|key| key.as_str()).collect()
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use axum::body::Bytes;
+    use axum::routing::post;
+    use axum::Router;
+    use opentelemetry::trace::TraceContextExt;
+    use serde_json::{json, Value};
+    use tokio::sync::mpsc;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_spans_exported_with_remote_parent() -> Result<()> {
+        // -- Setup & Fixtures
+        // Stand-in for the collector, forwarding the bodies received.
+        let (tx, mut rx) = mpsc::unbounded_channel::<Bytes>();
+        let collector = Router::new().route(
+            "/v1/traces",
+            post(move |body: Bytes| async move {
+                let _ = tx.send(body);
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        tokio::spawn(async move { axum::serve(listener, collector).await });
+
+        let fx_trace_id = "4bf92f3577b34da6a3ce929d0e0e4736";
+        let mut fx_headers = HeaderMap::new();
+        fx_headers.insert("traceparent", format!("00-{fx_trace_id}-00f067aa0ba902b7-01").parse()?);
+
+        let provider = new_tracer_provider(&endpoint, "webapi_test")?;
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("webapi")));
+
+        // -- Exec
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!("request", request_id = "fx_req_id");
+            span.set_parent(extract_context(&fx_headers));
+            span.in_scope(|| tracing::info_span!("db.query", db.operation = "get").in_scope(|| {}));
+        });
+        // Blocking, the exporter uses a blocking http client.
+        tokio::task::spawn_blocking(move || provider.shutdown()).await??;
+
+        // -- Check
+        let body = rx.recv().await.expect("Should have received the spans");
+        let body: Value = serde_json::from_slice(&body)?;
+        let resource_spans = &body["resourceSpans"][0];
+        let attributes = resource_spans["resource"]["attributes"].as_array().expect("Should have attributes");
+        let service_name = attributes.iter().find(|attr| attr["key"] == "service.name");
+        assert_eq!(service_name.map(|attr| &attr["value"]["stringValue"]), Some(&json!("webapi_test")));
+        let spans = resource_spans["scopeSpans"][0]["spans"].as_array().expect("Should have spans");
+        let names: Vec<&Value> = spans.iter().map(|span| &span["name"]).collect();
+        assert_eq!(names, [&json!("db.query"), &json!("request")]);
+        for span in spans {
+            assert_eq!(span["traceId"], fx_trace_id, "Should share the remote trace id");
+        }
+        assert_eq!(spans[0]["parentSpanId"], spans[1]["spanId"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_context_invalid() -> Result<()> {
+        // -- Setup & Fixtures
+        let mut fx_headers = HeaderMap::new();
+        fx_headers.insert("traceparent", "00-not-a-trace-01".parse()?);
+
+        // -- Exec
+        let cx = extract_context(&fx_headers);
+
+        // -- Check
+        assert!(!cx.span().span_context().is_valid());
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -1,5 +1,7 @@
 mod error;
 pub mod time_utils;
 pub mod base64_utils;
+pub mod regex_utils;
+pub mod task_utils;
 
 pub use self::error::{Error, Result};
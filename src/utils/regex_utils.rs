@@ -0,0 +1,7 @@
+use lazy_regex::{lazy_regex, Lazy, Regex};
+
+/// At least one non-whitespace character.
+pub static RE_NOT_BLANK: Lazy<Regex> = lazy_regex!(r"\S");
+
+/// Letters, digits and `_`, `.`, `-` only.
+pub static RE_USERNAME: Lazy<Regex> = lazy_regex!(r"^[a-zA-Z0-9_.\-]+$");
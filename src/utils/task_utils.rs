@@ -0,0 +1,24 @@
+use std::future::Future;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+/// A background loop, expected to run as long as the process.
+#[derive(Clone)]
+pub struct WorkerHandle {
+    pub name: &'static str,
+    handle: Arc<JoinHandle<()>>,
+}
+
+impl WorkerHandle {
+    /// `false` once the loop returned or panicked.
+    pub fn is_running(&self) -> bool {
+        !self.handle.is_finished()
+    }
+}
+
+pub fn spawn_worker<F>(name: &'static str, future: F) -> WorkerHandle
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    WorkerHandle { name, handle: Arc::new(tokio::spawn(future)) }
+}
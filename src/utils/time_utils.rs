@@ -1,4 +1,5 @@
 use time::format_description::well_known::Rfc3339;
+use std::time::Duration as StdDuration;
 use time::{Duration, OffsetDateTime};
 use crate::utils::{Error, Result};
 
@@ -18,3 +19,33 @@ pub fn now_utc_plus_sec_str(sec: f64) -> String {
 pub fn parse_utc(moment: &str) -> Result<OffsetDateTime> {
     OffsetDateTime::parse(moment, &Rfc3339).map_err(|_| Error::DateFailParse(moment.to_string()))
 }
+
+/// `base * 2^(attempt - 1)`, capped to `max`.
+pub fn backoff_delay(base: StdDuration, max: StdDuration, attempt: i32) -> StdDuration {
+    let exp = attempt.saturating_sub(1).clamp(0, 16) as u32;
+
+    base.saturating_mul(2u32.pow(exp)).min(max)
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_backoff_delay_capped() -> Result<()> {
+        // -- Fixtures
+        let fx_base = StdDuration::from_secs(10);
+        let fx_max = StdDuration::from_secs(60);
+
+        // -- Exec & Check
+        assert_eq!(backoff_delay(fx_base, fx_max, 1), StdDuration::from_secs(10));
+        assert_eq!(backoff_delay(fx_base, fx_max, 3), StdDuration::from_secs(40));
+        assert_eq!(backoff_delay(fx_base, fx_max, 4), StdDuration::from_secs(60));
+        assert_eq!(backoff_delay(fx_base, fx_max, 99), StdDuration::from_secs(60));
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -7,6 +7,8 @@ use serde::Serialize;
 use serde_with::{serde_as, DisplayFromStr};
 use tracing::debug;
 use crate::web::middlewares::auth::CtxExtractorError;
+use crate::web::validation::FieldViolation;
+use crate::log::redactor;
 
 pub type Result<T> = core::result::Result<T, Error>;
 
@@ -18,22 +20,31 @@ pub enum Error {
     LoginFailUserNotFound,
     LoginFailUserHasNoPassword,
     LoginFailUserNotValidated { user_id: i64 },
+    LoginFailUserDisabled { user_id: i64 },
     LoginFailPasswordNotMatching { user_id: i64 },
 
     AuthFailNoAuthToken,
     AuthFailTokenWrongFormat,
     AuthFailNoContext,
-
-    TicketDeleteIdNotFound { id: u64 },
+    AccessDeniedNotAdmin { user_id: i64 },
 
     #[from]
     CtxExt(CtxExtractorError),
 
+    ReqStampNotInReqExt,
+
+    RateLimited { retry_after_sec: u64 },
+    PayloadTooLarge { limit_bytes: usize },
+    ReqBodyReadFail(String),
+    ReqJsonBodyFail { reason: &'static str },
+
+    RpcFailJsonRequest,
     RpcMethodUnknown(String),
     RpcMissingParams { rpc_method: String },
     RpcFailJsonParams { rpc_method: String },
 
-    ModelError(),
+    ValidationFail { violations: Vec<FieldViolation> },
+
     #[from]
     Model(model::Error),
     #[from]
@@ -46,7 +57,7 @@ pub enum Error {
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        debug!("{:<12} - model::Error {self:?}", "INTO_RES");
+        debug!("{:<12} - model::Error {}", "INTO_RES", redactor().redact_json(&self));
 
         // Create a placeholder Axum reponse.
         let mut response = StatusCode::INTERNAL_SERVER_ERROR.into_response();
@@ -59,6 +70,16 @@ impl IntoResponse for Error {
 }
 
 impl Error {
+    /// The user of a failed login, when it was found.
+    pub fn login_user_id(&self) -> Option<i64> {
+        match self {
+            Error::LoginFailUserNotValidated { user_id }
+            | Error::LoginFailUserDisabled { user_id }
+            | Error::LoginFailPasswordNotMatching { user_id } => Some(*user_id),
+            _ => None,
+        }
+    }
+
     pub fn client_status_and_error(&self) -> (StatusCode, ClientError) {
         use web::Error::*;
 
@@ -67,6 +88,7 @@ impl Error {
             LoginFail
             | LoginFailUserNotFound
             | LoginFailUserNotValidated { .. }
+            | LoginFailUserDisabled { .. }
             | LoginFailPasswordNotMatching { .. } => {
                 (StatusCode::FORBIDDEN, ClientError::LOGIN_FAIL)
             }
@@ -79,13 +101,103 @@ impl Error {
 
             CtxExt(_) => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
 
+            AccessDeniedNotAdmin { .. } => (StatusCode::FORBIDDEN, ClientError::ACCESS_DENIED),
+
+            RateLimited { retry_after_sec } => (
+                StatusCode::TOO_MANY_REQUESTS,
+                ClientError::RATE_LIMITED { retry_after_sec: *retry_after_sec },
+            ),
+
+            PayloadTooLarge { limit_bytes } => (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                ClientError::PAYLOAD_TOO_LARGE { limit_bytes: *limit_bytes },
+            ),
+
+            ReqBodyReadFail(_) => (
+                StatusCode::BAD_REQUEST,
+                ClientError::INVALID_PARAMS {
+                    violations: vec![FieldViolation::new("request", "unreadable")],
+                },
+            ),
+
+            ReqJsonBodyFail { reason } => (
+                StatusCode::BAD_REQUEST,
+                ClientError::INVALID_PARAMS {
+                    violations: vec![FieldViolation::new("request", *reason)],
+                },
+            ),
+
+            RpcFailJsonRequest => (
+                StatusCode::BAD_REQUEST,
+                ClientError::INVALID_PARAMS {
+                    violations: vec![FieldViolation::new("request", "invalid_json")],
+                },
+            ),
+
+            RpcMissingParams { .. } => (
+                StatusCode::BAD_REQUEST,
+                ClientError::INVALID_PARAMS {
+                    violations: vec![FieldViolation::new("params", "required")],
+                },
+            ),
+
+            RpcFailJsonParams { .. } => (
+                StatusCode::BAD_REQUEST,
+                ClientError::INVALID_PARAMS {
+                    violations: vec![FieldViolation::new("params", "invalid_json")],
+                },
+            ),
+
+            ValidationFail { violations } => (
+                StatusCode::BAD_REQUEST,
+                ClientError::INVALID_PARAMS { violations: violations.clone() },
+            ),
+
             Model(model::Error::EntityNotFound { entity, id }) => {
                 (StatusCode::NOT_FOUND, ClientError::ENTITY_NOT_FOUND { entity, id: *id })
             }
 
-            TicketDeleteIdNotFound { .. } => {
-                (StatusCode::BAD_REQUEST, ClientError::INVALID_PARAMS)
-            }
+            Model(model::Error::InvalidState { entity, id, state }) => (
+                StatusCode::CONFLICT,
+                ClientError::INVALID_STATE { entity, id: *id, state: state.clone() },
+            ),
+
+            Model(model::Error::UniqueViolation { table, constraint }) => (
+                StatusCode::CONFLICT,
+                ClientError::DUPLICATE_VALUE {
+                    entity: table.clone(),
+                    constraint: constraint.clone(),
+                },
+            ),
+
+            Model(model::Error::ForeignKeyViolation { table, constraint }) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::INVALID_REFERENCE {
+                    entity: table.clone(),
+                    constraint: constraint.clone(),
+                },
+            ),
+
+            Model(model::Error::NotNullViolation { table, column }) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::MISSING_VALUE {
+                    entity: table.clone(),
+                    column: column.clone(),
+                },
+            ),
+
+            Model(model::Error::CheckViolation { table, constraint }) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::CONSTRAINT_VIOLATION {
+                    entity: table.clone(),
+                    constraint: constraint.clone(),
+                },
+            ),
+
+            Model(model::Error::StringTooLong { .. }) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ClientError::VALUE_TOO_LONG,
+            ),
 
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -112,7 +224,17 @@ impl std::error::Error for Error {}
 pub enum ClientError {
     LOGIN_FAIL,
     NO_AUTH,
-    INVALID_PARAMS,
+    ACCESS_DENIED,
+    RATE_LIMITED { retry_after_sec: u64 },
+    PAYLOAD_TOO_LARGE { limit_bytes: usize },
+    INVALID_PARAMS { violations: Vec<FieldViolation> },
     SERVICE_ERROR,
     ENTITY_NOT_FOUND { entity: &'static str, id: i64 },
+    INVALID_STATE { entity: &'static str, id: i64, state: String },
+
+    DUPLICATE_VALUE { entity: Option<String>, constraint: Option<String> },
+    INVALID_REFERENCE { entity: Option<String>, constraint: Option<String> },
+    MISSING_VALUE { entity: Option<String>, column: Option<String> },
+    CONSTRAINT_VIOLATION { entity: Option<String>, constraint: Option<String> },
+    VALUE_TOO_LONG,
 }
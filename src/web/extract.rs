@@ -0,0 +1,91 @@
+use async_trait::async_trait;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{FromRequest, Request};
+use axum::Json;
+use serde::de::DeserializeOwned;
+use tracing::debug;
+use crate::web::{Error, Result};
+
+/// Like `Json`, but its rejections are a `web::Error`, answered as
+/// `INVALID_PARAMS` like the other client errors, rather than in plain text.
+pub struct JsonBody<T>(pub T);
+
+#[async_trait]
+impl<S: Send + Sync, T: DeserializeOwned> FromRequest<S> for JsonBody<T> {
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self> {
+        debug!("{:<12} - JsonBody", "EXTRACTOR");
+
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(JsonBody(value)),
+            Err(rejection) => Err(rejection_error(rejection)),
+        }
+    }
+}
+
+/// The rejection text may quote the body, it is not kept.
+fn rejection_error(rejection: JsonRejection) -> Error {
+    let reason = match rejection {
+        JsonRejection::MissingJsonContentType(_) => "content_type_not_json",
+        JsonRejection::JsonSyntaxError(_) => "invalid_json",
+        JsonRejection::JsonDataError(_) => "invalid_data",
+        JsonRejection::BytesRejection(rejection) => return Error::ReqBodyReadFail(rejection.body_text()),
+        _ => "invalid_json",
+    };
+
+    Error::ReqJsonBodyFail { reason }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use axum::body::Body;
+    use axum::http::header::CONTENT_TYPE;
+    use crate::model::task::TaskForCreate;
+
+    fn fx_request(content_type: &str, body: &'static str) -> Request {
+        Request::post("/api/tasks")
+            .header(CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_json_body_err_reasons() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            (fx_request("text/plain", r#"{"title": "fx"}"#), "content_type_not_json"),
+            (fx_request("application/json", r#"{"title": "#), "invalid_json"),
+            (fx_request("application/json", r#"{"title": 1}"#), "invalid_data"),
+        ];
+
+        for (fx_req, fx_reason) in fx_cases {
+            // -- Exec
+            let res = JsonBody::<TaskForCreate>::from_request(fx_req, &()).await;
+
+            // -- Check
+            assert!(
+                matches!(&res, Err(Error::ReqJsonBodyFail { reason }) if *reason == fx_reason),
+                "Should have failed with `{fx_reason}`"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_json_body_ok() -> Result<()> {
+        // -- Exec
+        let JsonBody(task_c) =
+            JsonBody::<TaskForCreate>::from_request(fx_request("application/json", r#"{"title": "fx"}"#), &()).await?;
+
+        // -- Check
+        assert_eq!(task_c.title, "fx");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,260 @@
+use crate::web::ClientError;
+
+/// Cookie holding the user's explicit language preference,
+/// which wins over the browser's `Accept-Language`.
+pub const LANG_COOKIE: &str = "lang";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Lang {
+    #[default]
+    En,
+    Fr,
+}
+
+impl Lang {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Fr => "fr",
+        }
+    }
+
+    /// Matches on the primary subtag only, so `fr-CA` resolves to `Fr`.
+    pub fn from_tag(tag: &str) -> Option<Lang> {
+        let primary = tag.trim().split(['-', '_']).next()?;
+
+        match primary.to_ascii_lowercase().as_str() {
+            "en" => Some(Lang::En),
+            "fr" => Some(Lang::Fr),
+            _ => None,
+        }
+    }
+
+    pub fn negotiate(preference: Option<&str>, accept_language: Option<&str>) -> Lang {
+        preference
+            .and_then(Lang::from_tag)
+            .or_else(|| accept_language.and_then(Lang::from_accept_language))
+            .unwrap_or_default()
+    }
+
+    /// Picks the supported language with the highest `q` weight.
+    fn from_accept_language(header: &str) -> Option<Lang> {
+        let mut best: Option<(Lang, f32)> = None;
+
+        for range in header.split(',') {
+            let mut parts = range.split(';');
+            let Some(lang) = parts.next().and_then(Lang::from_tag) else {
+                continue;
+            };
+            let q = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .and_then(|q| q.parse::<f32>().ok())
+                .unwrap_or(1.0);
+
+            if q > 0.0 && !matches!(best, Some((_, best_q)) if best_q >= q) {
+                best = Some((lang, q));
+            }
+        }
+
+        best.map(|(lang, _)| lang)
+    }
+}
+
+// region: -- ClientError Messages
+
+impl ClientError {
+    pub fn message(&self, lang: Lang) -> String {
+        match lang {
+            Lang::En => self.message_en(),
+            Lang::Fr => self.message_fr(),
+        }
+    }
+
+    fn message_en(&self) -> String {
+        use ClientError::*;
+
+        match self {
+            LOGIN_FAIL => "Login failed.".to_string(),
+            NO_AUTH => "Authentication is required.".to_string(),
+            ACCESS_DENIED => "Access denied.".to_string(),
+            RATE_LIMITED { retry_after_sec } => {
+                format!("Too many requests, retry in {retry_after_sec} second(s).")
+            }
+            PAYLOAD_TOO_LARGE { limit_bytes } => {
+                format!("The request body exceeds {limit_bytes} bytes.")
+            }
+            INVALID_PARAMS { violations } => {
+                format!("{} invalid parameter(s).", violations.len())
+            }
+            SERVICE_ERROR => "An internal service error occurred.".to_string(),
+            ENTITY_NOT_FOUND { entity, id } => format!("No {entity} with id {id}."),
+            INVALID_STATE { entity, id, state } => {
+                format!("The {entity} {id} cannot be changed while '{state}'.")
+            }
+            DUPLICATE_VALUE { entity, .. } => {
+                format!("A {} with the same value already exists.", or_entity(entity))
+            }
+            INVALID_REFERENCE { entity, .. } => {
+                format!("The {} references a missing entity.", or_entity(entity))
+            }
+            MISSING_VALUE { column, .. } => {
+                format!("A value is required for '{}'.", or_column(column))
+            }
+            CONSTRAINT_VIOLATION { entity, .. } => {
+                format!("The {} does not satisfy a constraint.", or_entity(entity))
+            }
+            VALUE_TOO_LONG => "A value is too long.".to_string(),
+        }
+    }
+
+    fn message_fr(&self) -> String {
+        use ClientError::*;
+
+        match self {
+            LOGIN_FAIL => "Échec de la connexion.".to_string(),
+            NO_AUTH => "Une authentification est requise.".to_string(),
+            ACCESS_DENIED => "Accès refusé.".to_string(),
+            RATE_LIMITED { retry_after_sec } => {
+                format!("Trop de requêtes, réessayez dans {retry_after_sec} seconde(s).")
+            }
+            PAYLOAD_TOO_LARGE { limit_bytes } => {
+                format!("Le corps de la requête dépasse {limit_bytes} octets.")
+            }
+            INVALID_PARAMS { violations } => {
+                format!("{} paramètre(s) invalide(s).", violations.len())
+            }
+            SERVICE_ERROR => "Une erreur interne du service est survenue.".to_string(),
+            ENTITY_NOT_FOUND { entity, id } => {
+                format!("{} avec l'identifiant {id}.", NounFr::of(Some(entity)).none())
+            }
+            INVALID_STATE { entity, id, state } => {
+                let noun = NounFr::of(Some(entity));
+                format!(
+                    "{} {id} ne peut pas être modifié{} à l'état '{state}'.",
+                    noun.definite(),
+                    noun.agreement()
+                )
+            }
+            DUPLICATE_VALUE { entity, .. } => {
+                format!("{} avec la même valeur existe déjà.", NounFr::of(entity.as_deref()).indefinite())
+            }
+            INVALID_REFERENCE { entity, .. } => {
+                format!("{} référence une entité inexistante.", NounFr::of(entity.as_deref()).definite())
+            }
+            MISSING_VALUE { column, .. } => {
+                format!("Une valeur est requise pour '{}'.", or_column(column))
+            }
+            CONSTRAINT_VIOLATION { entity, .. } => {
+                format!("{} ne respecte pas une contrainte.", NounFr::of(entity.as_deref()).definite())
+            }
+            VALUE_TOO_LONG => "Une valeur est trop longue.".to_string(),
+        }
+    }
+}
+
+fn or_entity(entity: &Option<String>) -> &str {
+    entity.as_deref().unwrap_or("entity")
+}
+
+/// The French name of an entity (table), with what its articles need.
+struct NounFr {
+    name: &'static str,
+    feminine: bool,
+    /// Starts with a vowel sound, `l'` rather than `le` / `la`.
+    elided: bool,
+}
+
+impl NounFr {
+    fn of(entity: Option<&str>) -> NounFr {
+        let (name, feminine, elided) = match entity {
+            Some("task") => ("tâche", true, false),
+            Some("ticket") => ("ticket", false, false),
+            Some("webhook") => ("webhook", false, false),
+            Some("webhook_delivery") => ("livraison de webhook", true, false),
+            Some("job") => ("tâche de fond", true, false),
+            Some("user") => ("utilisateur", false, true),
+            Some("audit_event") => ("événement d'audit", false, true),
+            _ => ("élément", false, true),
+        };
+
+        NounFr { name, feminine, elided }
+    }
+
+    /// At the start of a sentence, e.g. `La tâche`.
+    fn definite(&self) -> String {
+        let article = match (self.elided, self.feminine) {
+            (true, _) => "L'",
+            (false, true) => "La ",
+            (false, false) => "Le ",
+        };
+
+        format!("{article}{}", self.name)
+    }
+
+    fn indefinite(&self) -> String {
+        format!("{} {}", if self.feminine { "Une" } else { "Un" }, self.name)
+    }
+
+    fn none(&self) -> String {
+        format!("{} {}", if self.feminine { "Aucune" } else { "Aucun" }, self.name)
+    }
+
+    /// The ending of a past participle agreeing with the noun.
+    fn agreement(&self) -> &'static str {
+        if self.feminine { "e" } else { "" }
+    }
+}
+
+fn or_column(column: &Option<String>) -> &str {
+    column.as_deref().unwrap_or("?")
+}
+
+// endregion: -- ClientError Messages
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_negotiate_accept_language_q_ok() -> Result<()> {
+        // -- Exec & Check
+        assert_eq!(Lang::negotiate(None, Some("de-DE, fr-CA;q=0.8, en;q=0.5")), Lang::Fr);
+        assert_eq!(Lang::negotiate(None, Some("fr;q=0.2, en-GB;q=0.9")), Lang::En);
+        assert_eq!(Lang::negotiate(None, Some("fr;q=0, de")), Lang::En);
+        assert_eq!(Lang::negotiate(None, None), Lang::En);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_negotiate_preference_wins() -> Result<()> {
+        // -- Exec & Check
+        assert_eq!(Lang::negotiate(Some("fr"), Some("en")), Lang::Fr);
+        assert_eq!(Lang::negotiate(Some("xx"), Some("fr")), Lang::Fr);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_fr_parameterized() -> Result<()> {
+        // -- Fixtures
+        let fx_not_found = ClientError::ENTITY_NOT_FOUND { entity: "task", id: 1001 };
+        let fx_invalid_state = ClientError::INVALID_STATE { entity: "job", id: 1002, state: "running".to_string() };
+        let fx_duplicate = ClientError::DUPLICATE_VALUE { entity: Some("user".to_string()), constraint: None };
+        let fx_invalid_reference = ClientError::INVALID_REFERENCE { entity: Some("user".to_string()), constraint: None };
+
+        // -- Exec & Check
+        assert_eq!(fx_not_found.message(Lang::Fr), "Aucune tâche avec l'identifiant 1001.");
+        assert_eq!(
+            fx_invalid_state.message(Lang::Fr),
+            "La tâche de fond 1002 ne peut pas être modifiée à l'état 'running'."
+        );
+        assert_eq!(fx_duplicate.message(Lang::Fr), "Un utilisateur avec la même valeur existe déjà.");
+        assert_eq!(fx_invalid_reference.message(Lang::Fr), "L'utilisateur référence une entité inexistante.");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -3,6 +3,7 @@ use crate::ctx::Ctx;
 use crate::model::user::{UserRepository, UserForAuth};
 use crate::model::DbContext;
 use crate::web::{set_token_cookie, AUTH_TOKEN};
+use crate::web::middlewares::stamp::ReqStamp;
 use crate::web::{Error, Result};
 
 use async_trait::async_trait;
@@ -14,7 +15,7 @@ use axum::middleware::Next;
 use axum::response::Response;
 use serde::Serialize;
 use tower_cookies::{Cookie, Cookies};
-use tracing::debug;
+use tracing::{debug, Span};
 use crate::web::Error::CtxExt;
 
 #[allow(dead_code)]
@@ -38,7 +39,13 @@ pub async fn mw_ctx_resolver(
 ) -> Result<Response> {
     debug!("{:<12} - mw_ctx_resolver", "MIDDLEWARE");
 
-    let ctx_ext_result = _ctx_resolve(db_context, &cookies).await;
+    let req_id = request.extensions().get::<ReqStamp>().map(|stamp| stamp.id.clone());
+    let ctx_ext_result = _ctx_resolve(db_context, &cookies)
+        .await
+        .map(|ctx| match req_id {
+            Some(req_id) => ctx.with_req_id(req_id),
+            None => ctx,
+        });
 
     if ctx_ext_result.is_err() &&
         !matches!(ctx_ext_result, Err(CtxExtractorError::TokenNotInCookie))
@@ -46,6 +53,10 @@ pub async fn mw_ctx_resolver(
         cookies.remove(Cookie::from(AUTH_TOKEN))
     }
 
+    if let Ok(ctx) = &ctx_ext_result {
+        Span::current().record("user_id", ctx.user_id());
+    }
+
     request.extensions_mut().insert(ctx_ext_result);
 
     Ok(next.run(request).await)
@@ -62,19 +73,55 @@ async fn _ctx_resolve(
 
     let token = token.parse::<Token>().map_err(|_| CtxExtractorError::TokenWrongFormat)?;
 
+    let user = user_for_token(&db_context, &token).await?;
+
+    set_token_cookie(cookies, &user.username, user.token_salt)
+        .map_err(|_| CtxExtractorError::CannotSetTokenCookie);
+
+    ctx_for_user(&user)
+}
+
+/// The token of the request, as refreshed by `mw_ctx_resolver`, for the
+/// long-lived connections which check it again with `ctx_from_token`.
+pub fn cookie_token(cookies: &Cookies) -> Result<Token> {
+    cookies
+        .get(AUTH_TOKEN)
+        .and_then(|c| c.value().parse::<Token>().ok())
+        .ok_or(Error::CtxExt(CtxExtractorError::TokenNotInCookie))
+}
+
+/// Without the cookie refresh, for the websocket calls and the SSE events,
+/// which check again the token of the request opening the connection.
+pub async fn ctx_from_token(db_context: &DbContext, token: &Token) -> CtxExtractorResult {
+    let user = user_for_token(db_context, token).await?;
+
+    ctx_for_user(&user)
+}
+
+async fn user_for_token(
+    db_context: &DbContext,
+    token: &Token,
+) -> core::result::Result<UserForAuth, CtxExtractorError> {
     let user: UserForAuth = UserRepository::
-    first_by_username(&Ctx::root_ctx(), &db_context, &token.identifier)
+    first_by_username(&Ctx::root_ctx(), db_context, &token.identifier)
         .await
         .map_err(|ex| CtxExtractorError::DbContextAccessError(ex.to_string()))?
         .ok_or(CtxExtractorError::UserNotFound)?;
 
-    validate_web_token(&token, user.token_salt)
+    if user.disabled {
+        return Err(CtxExtractorError::UserDisabled);
+    }
+
+    validate_web_token(token, user.token_salt)
         .map_err(|_| CtxExtractorError::FailValidateToken)?;
 
-    set_token_cookie(cookies, &user.username, user.token_salt)
-        .map_err(|_| CtxExtractorError::CannotSetTokenCookie);
+    Ok(user)
+}
 
-    Ctx::new(user.id).map_err(|ex| CtxExtractorError::CtxCreateFail(ex.to_string()))
+fn ctx_for_user(user: &UserForAuth) -> CtxExtractorResult {
+    Ctx::new(user.id)
+        .map(|ctx| ctx.with_admin(user.is_admin))
+        .map_err(|ex| CtxExtractorError::CtxCreateFail(ex.to_string()))
 }
 
 #[async_trait]
@@ -100,9 +147,59 @@ pub enum CtxExtractorError {
     TokenNotInCookie,
     TokenWrongFormat,
     UserNotFound,
+    UserDisabled,
     DbContextAccessError(String),
     FailValidateToken,
     CannotSetTokenCookie,
     CtxNotInRequestExt,
     CtxCreateFail(String),
 }
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use crate::model::user::UserForCreate;
+    use crate::token::generate_web_token;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_ctx_from_token_err_revoked() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_user_c = UserForCreate {
+            username: "test_ctx_from_token_err_revoked".to_string(),
+            pwd_clear: "welcome-01".to_string(),
+        };
+        let id = UserRepository::create(&ctx, &db_context, fx_user_c).await?;
+        let user: UserForAuth = UserRepository::get(&ctx, &db_context, id).await?;
+        let fx_token = generate_web_token(&user.username, user.token_salt)?;
+
+        // -- Exec
+        let res_valid = ctx_from_token(&db_context, &fx_token).await;
+        UserRepository::revoke_tokens(&ctx, &db_context, id).await?;
+        let res_revoked = ctx_from_token(&db_context, &fx_token).await;
+        let user: UserForAuth = UserRepository::get(&ctx, &db_context, id).await?;
+        let fx_token = generate_web_token(&user.username, user.token_salt)?;
+        UserRepository::set_disabled(&ctx, &db_context, id, true).await?;
+        let res_disabled = ctx_from_token(&db_context, &fx_token).await;
+
+        // -- Check
+        assert_eq!(res_valid.map(|ctx| ctx.user_id()).ok(), Some(id));
+        assert!(
+            matches!(res_revoked, Err(CtxExtractorError::FailValidateToken)),
+            "Should be revoked, was `{res_revoked:?}`"
+        );
+        assert!(
+            matches!(res_disabled, Err(CtxExtractorError::UserDisabled)),
+            "Should be disabled, was `{res_disabled:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,130 @@
+use axum::body::{to_bytes, Body};
+use axum::http::header::CONTENT_LENGTH;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use http_body_util::LengthLimitError;
+use tracing::debug;
+use crate::config::config;
+use crate::web::middlewares::response_mapper::RPC_PATH;
+use crate::web::{Error, Result};
+
+const LOGIN_PATH: &str = "/api/login";
+/// A username and a password.
+const LOGIN_BODY_LIMIT: usize = 4 * 1024;
+
+/// Buffers the request body up to the limit of its route, so the handlers
+/// never see an oversize payload. Replaces the axum default limit, which is
+/// disabled.
+pub async fn mw_body_limit(req: Request<Body>, next: Next) -> Result<Response> {
+    let limit_bytes = body_limit_for(req.uri().path());
+
+    // Refused before reading, when announced.
+    let content_length = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if content_length.is_some_and(|len| len > limit_bytes) {
+        debug!("{:<12} - mw_body_limit - content-length over {limit_bytes}", "MIDDLEWARE");
+        return Err(Error::PayloadTooLarge { limit_bytes });
+    }
+
+    let (parts, body) = req.into_parts();
+    let bytes = to_bytes(body, limit_bytes).await.map_err(|ex| {
+        let ex = ex.into_inner();
+        if ex.downcast_ref::<LengthLimitError>().is_some() {
+            Error::PayloadTooLarge { limit_bytes }
+        } else {
+            Error::ReqBodyReadFail(ex.to_string())
+        }
+    })?;
+
+    Ok(next.run(Request::from_parts(parts, Body::from(bytes))).await)
+}
+
+fn body_limit_for(path: &str) -> usize {
+    match path {
+        LOGIN_PATH => LOGIN_BODY_LIMIT,
+        RPC_PATH => config().BODY_LIMIT_RPC_BYTES,
+        _ => config().BODY_LIMIT_BYTES,
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::middlewares::response_mapper::mw_response_mapper;
+    use crate::web::middlewares::stamp::mw_req_stamp;
+    use anyhow::Result;
+    use axum::routing::post;
+    use axum::{middleware, Router};
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tower_cookies::CookieManagerLayer;
+
+    async fn serve_fx_routes() -> Result<String> {
+        let echo_len = post(|body: String| async move { body.len().to_string() });
+        let routes = Router::new()
+            .route("/api/login", echo_len.clone())
+            .route("/api/tasks", echo_len)
+            .layer(middleware::from_fn(mw_body_limit))
+            .layer(middleware::map_response(mw_response_mapper))
+            .layer(CookieManagerLayer::new())
+            .layer(middleware::from_fn(mw_req_stamp));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?.to_string();
+        tokio::spawn(async move { axum::serve(listener, routes).await });
+
+        Ok(addr)
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_per_route() -> Result<()> {
+        // -- Setup & Fixtures
+        let addr = serve_fx_routes().await?;
+        let fx_body = "x".repeat(LOGIN_BODY_LIMIT + 1);
+        let client = reqwest::Client::new();
+
+        // -- Exec
+        let login_res = client.post(format!("http://{addr}/api/login")).body(fx_body.clone()).send().await?;
+        let tasks_res = client.post(format!("http://{addr}/api/tasks")).body(fx_body.clone()).send().await?;
+
+        // -- Check
+        assert_eq!(login_res.status(), 413);
+        let body: Value = login_res.json().await?;
+        assert_eq!(body["code"], "PAYLOAD_TOO_LARGE");
+        assert_eq!(body["limit_bytes"], json!(LOGIN_BODY_LIMIT));
+        assert_eq!(tasks_res.status(), 200, "Should be under the default limit");
+        assert_eq!(tasks_res.text().await?, fx_body.len().to_string());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_body_limit_chunked() -> Result<()> {
+        // -- Setup & Fixtures
+        let addr = serve_fx_routes().await?;
+        let fx_chunk = "x".repeat(LOGIN_BODY_LIMIT + 1);
+        // Without a content-length, the body is only refused once read.
+        let fx_request = format!(
+            "POST /api/login HTTP/1.1\r\nHost: {addr}\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+             {:x}\r\n{fx_chunk}\r\n0\r\n\r\n",
+            fx_chunk.len()
+        );
+
+        // -- Exec
+        let mut stream = tokio::net::TcpStream::connect(&addr).await?;
+        stream.write_all(fx_request.as_bytes()).await?;
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await?;
+
+        // -- Check
+        assert!(response.starts_with("HTTP/1.1 413"), "Should be refused, got {response}");
+        assert!(response.contains("PAYLOAD_TOO_LARGE"));
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -2,4 +2,8 @@
 
 pub mod response_mapper;
 pub mod auth;
+pub mod body_limit;
+pub mod rate_limit;
+pub mod security;
+pub mod stamp;
 
@@ -0,0 +1,64 @@
+use axum::body::Body;
+use axum::http::Request;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::debug;
+use crate::config::config;
+use crate::ctx::Ctx;
+use crate::rate_limit::{limiter, Decision, Quota};
+use crate::web::middlewares::stamp::ReqStamp;
+use crate::web::{Error, Result};
+
+/// Per client IP, to slow down the credential stuffing. Without the client
+/// IP (e.g. in tests), the request is let through.
+pub async fn mw_rate_limit_login(req_stamp: ReqStamp, req: Request<Body>, next: Next) -> Result<Response> {
+    debug!("{:<12} - mw_rate_limit_login", "MIDDLEWARE");
+
+    if let (Some(quota), Some(client_ip)) = (config().RATE_LIMIT_LOGIN, req_stamp.client_ip) {
+        check(&format!("login:{client_ip}"), quota).await?;
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Per user, before the body is read. The method quotas are checked once
+/// the RPC request is parsed, see `check_rpc_method`.
+pub async fn mw_rate_limit_rpc(ctx: Ctx, req: Request<Body>, next: Next) -> Result<Response> {
+    debug!("{:<12} - mw_rate_limit_rpc", "MIDDLEWARE");
+
+    check_rpc_user(&ctx).await?;
+
+    Ok(next.run(req).await)
+}
+
+/// For the calls over the websocket, which are not requests of their own.
+pub async fn check_rpc_user(ctx: &Ctx) -> Result<()> {
+    match config().RATE_LIMIT_RPC {
+        Some(quota) => check(&format!("rpc:{}", ctx.user_id()), quota).await,
+        None => Ok(()),
+    }
+}
+
+pub async fn check_rpc_method(ctx: &Ctx, rpc_method: &str) -> Result<()> {
+    let quota = config()
+        .RATE_LIMIT_RPC_METHODS
+        .iter()
+        .find(|(method, _)| method == rpc_method)
+        .map(|(_, quota)| *quota);
+
+    match quota {
+        Some(quota) => check(&format!("rpc:{}:{rpc_method}", ctx.user_id()), quota).await,
+        None => Ok(()),
+    }
+}
+
+async fn check(key: &str, quota: Quota) -> Result<()> {
+    match limiter().check(key, quota).await {
+        Decision::Allowed => Ok(()),
+        Decision::Limited { retry_after } => {
+            debug!("{:<12} - {key} limited for {retry_after:?}", "RATE_LIMIT");
+            // `Retry-After` is in whole seconds, rounded up so the retry is not limited again.
+            Err(Error::RateLimited { retry_after_sec: retry_after.as_secs_f64().ceil().max(1.) as u64 })
+        }
+    }
+}
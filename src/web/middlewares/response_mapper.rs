@@ -1,55 +1,212 @@
 use std::sync::Arc;
-use axum::http::{Method, Uri};
+use axum::http::header::{ACCEPT, ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_TYPE, RETRY_AFTER};
+use axum::http::{HeaderMap, HeaderValue, Method, StatusCode, Uri};
+use axum::extract::MatchedPath;
 use axum::Json;
 use axum::response::{IntoResponse, Response};
-use serde_json::{json, to_value};
+use serde_json::{json, to_value, Value};
+use tower_cookies::Cookies;
 use tracing::debug;
-use uuid::Uuid;
 use crate::ctx::Ctx;
-use crate::log::log_request;
+use crate::log::{log_request, redactor, RequestLog};
+use crate::metrics::{metrics, RequestMetrics, ROUTE_FALLBACK};
 use crate::web;
-use crate::web::rpc::RpcInfo;
+use crate::web::ClientError;
+use crate::web::i18n::{Lang, LANG_COOKIE};
+use crate::web::middlewares::stamp::ReqStamp;
+use crate::web::rpc::{rpc_error_body, RpcInfo};
 
+pub(crate) const RPC_PATH: &str = "/api/rpc";
+const PROBLEM_JSON: &str = "application/problem+json";
+
+// One argument per extractor.
+#[allow(clippy::too_many_arguments)]
 pub async fn mw_response_mapper(
     ctx: Option<Ctx>,
+    req_stamp: ReqStamp,
+    matched_path: Option<MatchedPath>,
     uri: Uri,
     req_method: Method,
+    headers: HeaderMap,
+    cookies: Cookies,
     res: Response,
 ) -> Response {
-    debug!("{:<12} - main_response_mapper - {res:?}", "RES_MAPPER");
+    debug!(
+        "{:<12} - main_response_mapper - {} {:?}",
+        "RES_MAPPER",
+        res.status(),
+        redactor().redact_headers(res.headers())
+    );
 
-    let uuid = Uuid::new_v4();
+    let req_id = req_stamp.id.as_str();
 
     let rpc_info = res.extensions().get::<RpcInfo>();
 
     let service_error = res.extensions().get::<Arc<web::Error>>().map(Arc::as_ref);
     let client_status_error = service_error.map(|se| se.client_status_and_error());
 
+    let lang = Lang::negotiate(
+        cookies.get(LANG_COOKIE).as_ref().map(|c| c.value()),
+        headers.get(ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()),
+    );
+
     let error_response =
         client_status_error
             .as_ref()
-            .map(|&(ref status_code, ref client_error)| {
-                let client_error = to_value(client_error).ok();
-                let message = client_error.as_ref().and_then(|v| v.get("message"));
-                let detail = client_error.as_ref().and_then(|v| v.get("detail"));
-                let client_error_body = json!({
-                    "id": rpc_info.as_ref().map(|rpc| rpc.id.clone()),
-                    "error": {
-                        "message": message,
-                        "data": {
-                            "req_uuid": uuid.to_string(),
-                            "detail": detail
-                        }
+            .map(|(status_code, client_error)| {
+                let mut response = match ErrorFormat::for_request(&uri, &headers) {
+                    ErrorFormat::JsonRpc => {
+                        let body = rpc_error_body(rpc_info, client_error, req_id, lang);
+                        debug!("CLIENT_ERROR_BODY: {}", redactor().redact(body.clone()));
+
+                        (*status_code, [(CONTENT_LANGUAGE, lang.code())], Json(body))
+                            .into_response()
                     }
-                });
-                debug!("CLIENT_ERROR_BODY: {client_error_body}");
+                    ErrorFormat::Problem => {
+                        let body = problem_body(*status_code, client_error, req_id, lang);
+                        debug!("CLIENT_ERROR_BODY: {}", redactor().redact(body.clone()));
 
-                (*status_code, Json(client_error_body)).into_response()
+                        let headers = [(CONTENT_TYPE, PROBLEM_JSON), (CONTENT_LANGUAGE, lang.code())];
+                        (*status_code, headers, Json(body)).into_response()
+                    }
+                };
+
+                if let ClientError::RATE_LIMITED { retry_after_sec } = client_error {
+                    response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(*retry_after_sec));
+                }
+
+                response
             });
 
-    let client_error = client_status_error.unzip().1;
-    let _ = log_request(uuid, req_method, uri, rpc_info, ctx, service_error, client_error).await;
+    let status = error_response.as_ref().unwrap_or(&res).status();
+    let duration = req_stamp.time_in.elapsed();
+    let client_error = client_status_error.as_ref().map(|(_, client_error)| client_error);
+    metrics().record_request(&RequestMetrics {
+        method: req_method.as_str(),
+        route: matched_path.as_ref().map_or(ROUTE_FALLBACK, |path| path.as_str()),
+        status: status.as_u16(),
+        rpc_method: rpc_info.map(|rpc| rpc.method.as_str()),
+        client_error: client_error.map(|client_error| client_error.as_ref()),
+        duration,
+    });
+
+    log_request(RequestLog {
+        req_id,
+        method: &req_method,
+        uri: &uri,
+        status: Some(status.as_u16()),
+        duration,
+        client_ip: req_stamp.client_ip,
+        rpc_info,
+        ctx: ctx.as_ref(),
+        service_error,
+        client_error,
+    });
 
     debug!("END OF REQUEST\n");
     error_response.unwrap_or(res)
 }
+
+/// The JSON-RPC envelope is only kept for the RPC endpoint, every other
+/// route (or a client explicitly asking for it) gets RFC 7807 problem details.
+enum ErrorFormat {
+    JsonRpc,
+    Problem,
+}
+
+impl ErrorFormat {
+    fn for_request(uri: &Uri, headers: &HeaderMap) -> Self {
+        let accepts_problem = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .any(|v| v.contains(PROBLEM_JSON));
+
+        if uri.path() == RPC_PATH && !accepts_problem {
+            ErrorFormat::JsonRpc
+        } else {
+            ErrorFormat::Problem
+        }
+    }
+}
+
+fn problem_body(
+    status_code: StatusCode,
+    client_error: &ClientError,
+    req_id: &str,
+    lang: Lang,
+) -> Value {
+    let code = client_error.as_ref();
+
+    let mut body = json!({
+        "type": format!("urn:webapi:problem:{}", code.to_lowercase().replace('_', "-")),
+        "title": status_code.canonical_reason().unwrap_or("Unknown"),
+        "status": status_code.as_u16(),
+        "detail": client_error.message(lang),
+        "instance": req_id,
+        "code": code,
+    });
+
+    // The client error detail fields become problem extension members.
+    let detail = to_value(client_error)
+        .ok()
+        .and_then(|mut v| v.get_mut("detail").map(Value::take));
+    if let (Some(Value::Object(members)), Some(body)) = (detail, body.as_object_mut()) {
+        for (name, value) in members {
+            body.entry(name).or_insert(value);
+        }
+    }
+
+    body
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_error_format_for_request() -> Result<()> {
+        // -- Fixtures
+        let fx_rpc_uri: Uri = "/api/rpc".parse()?;
+        let fx_login_uri: Uri = "/api/login".parse()?;
+        let mut fx_problem_headers = HeaderMap::new();
+        fx_problem_headers.insert(ACCEPT, HeaderValue::from_static(PROBLEM_JSON));
+
+        // -- Exec & Check
+        assert!(matches!(ErrorFormat::for_request(&fx_rpc_uri, &HeaderMap::new()), ErrorFormat::JsonRpc));
+        assert!(matches!(ErrorFormat::for_request(&fx_rpc_uri, &fx_problem_headers), ErrorFormat::Problem));
+        assert!(matches!(ErrorFormat::for_request(&fx_login_uri, &HeaderMap::new()), ErrorFormat::Problem));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_problem_body_ok() -> Result<()> {
+        // -- Fixtures
+        let fx_req_id = "fx-req-id";
+        let fx_client_error = ClientError::ENTITY_NOT_FOUND { entity: "task", id: 1001 };
+
+        // -- Exec
+        let body = problem_body(StatusCode::NOT_FOUND, &fx_client_error, fx_req_id, Lang::En);
+
+        // -- Check
+        assert_eq!(
+            body,
+            json!({
+                "type": "urn:webapi:problem:entity-not-found",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "No task with id 1001.",
+                "instance": fx_req_id,
+                "code": "ENTITY_NOT_FOUND",
+                "entity": "task",
+                "id": 1001,
+            })
+        );
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,92 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+use axum::body::Body;
+use axum::http::header::{
+    ACCEPT, ACCEPT_LANGUAGE, CONTENT_LANGUAGE, CONTENT_SECURITY_POLICY, CONTENT_TYPE, RETRY_AFTER,
+    STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS, X_FRAME_OPTIONS,
+};
+use axum::http::{HeaderName, HeaderValue, Method, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+use crate::config::{config, Config};
+use crate::web::middlewares::stamp::REQUEST_ID_HEADER;
+
+const CORS_MAX_AGE: Duration = Duration::from_secs(600);
+const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// None when no origin is allowed, the browsers then refuse the cross-origin
+/// calls. The origins are checked by the config.
+pub fn cors_layer(config: &Config) -> Option<CorsLayer> {
+    if config.CORS_ALLOWED_ORIGINS.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if config.CORS_ALLOWED_ORIGINS.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .CORS_ALLOWED_ORIGINS
+                .iter()
+                .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+        )
+    };
+
+    let layer = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::DELETE])
+        .allow_headers([
+            ACCEPT,
+            ACCEPT_LANGUAGE,
+            CONTENT_TYPE,
+            HeaderName::from_static(REQUEST_ID_HEADER),
+            HeaderName::from_static(TRACEPARENT_HEADER),
+        ])
+        .expose_headers([
+            CONTENT_LANGUAGE,
+            RETRY_AFTER,
+            HeaderName::from_static(REQUEST_ID_HEADER),
+        ])
+        .allow_credentials(config.CORS_ALLOW_CREDENTIALS)
+        .max_age(CORS_MAX_AGE);
+
+    Some(layer)
+}
+
+/// On every response, static files included. A header already set by the
+/// handler is kept.
+pub async fn mw_security_headers(req: Request<Body>, next: Next) -> Response {
+    let mut res = next.run(req).await;
+
+    let headers = res.headers_mut();
+    for (name, value) in security_headers() {
+        if !headers.contains_key(name) {
+            headers.insert(name.clone(), value.clone());
+        }
+    }
+
+    res
+}
+
+fn security_headers() -> &'static [(HeaderName, HeaderValue)] {
+    static INSTANCE: OnceLock<Vec<(HeaderName, HeaderValue)>> = OnceLock::new();
+
+    INSTANCE.get_or_init(|| {
+        let config = config();
+        let mut headers = vec![
+            (X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff")),
+            (X_FRAME_OPTIONS, HeaderValue::from_static("DENY")),
+        ];
+        // Checked by the config.
+        if let Some(csp) = config.CONTENT_SECURITY_POLICY.as_ref().and_then(|csp| HeaderValue::from_str(csp).ok()) {
+            headers.push((CONTENT_SECURITY_POLICY, csp));
+        }
+        if config.HSTS_MAX_AGE_SEC > 0 {
+            let hsts = format!("max-age={}; includeSubDomains", config.HSTS_MAX_AGE_SEC);
+            headers.push((STRICT_TRANSPORT_SECURITY, HeaderValue::from_str(&hsts).expect("valid hsts header")));
+        }
+
+        headers
+    })
+}
@@ -0,0 +1,140 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::Instant;
+use async_trait::async_trait;
+use axum::body::Body;
+use axum::extract::{ConnectInfo, FromRequestParts, MatchedPath};
+use axum::http::request::Parts;
+use axum::http::{HeaderMap, HeaderValue, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::field::Empty;
+use tracing::{debug, info_span, Instrument};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use uuid::Uuid;
+use crate::metrics::ROUTE_FALLBACK;
+use crate::telemetry;
+use crate::web::{Error, Result};
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+const REQUEST_ID_MAX_LEN: usize = 128;
+
+/// Set when the request enters the stack, for the id and duration of the whole request.
+#[derive(Debug, Clone)]
+pub struct ReqStamp {
+    /// From the `X-Request-Id` header, or a new uuid.
+    pub id: String,
+    pub time_in: Instant,
+    /// The peer address, None when served without the connect info (e.g. in tests).
+    pub client_ip: Option<IpAddr>,
+}
+
+/// The outermost layer, opening the request span and echoing the request id
+/// on every response.
+///
+/// The inner layers and handlers record `user_id` and `rpc_method` on the span,
+/// via `Span::current()`.
+pub async fn mw_req_stamp(mut req: Request<Body>, next: Next) -> Response {
+    let stamp = ReqStamp {
+        id: req_id_from_headers(req.headers()).unwrap_or_else(|| Uuid::new_v4().to_string()),
+        time_in: Instant::now(),
+        client_ip: req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip()),
+    };
+    let req_id_header = HeaderValue::from_str(&stamp.id).expect("req id is a valid header value");
+
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str())
+        .unwrap_or(ROUTE_FALLBACK);
+    let span = info_span!(
+        "request",
+        otel.name = format!("{} {route}", req.method()),
+        otel.kind = "server",
+        request_id = stamp.id,
+        http.request.method = %req.method(),
+        http.route = route,
+        http.response.status_code = Empty,
+        user_id = Empty,
+        rpc_method = Empty,
+    );
+    span.set_parent(telemetry::extract_context(req.headers()));
+
+    req.extensions_mut().insert(stamp);
+
+    async move {
+        debug!("{:<12} - mw_req_stamp", "MIDDLEWARE");
+
+        let mut res = next.run(req).await;
+        tracing::Span::current().record("http.response.status_code", res.status().as_u16());
+        res.headers_mut().insert(REQUEST_ID_HEADER, req_id_header);
+
+        res
+    }
+    .instrument(span)
+    .await
+}
+
+/// The incoming id is written in the logs and responses, so only a short
+/// token is honored, e.g. a uuid. Anything else gets a new id.
+fn req_id_from_headers(headers: &HeaderMap) -> Option<String> {
+    let req_id = headers.get(REQUEST_ID_HEADER)?.to_str().ok()?;
+    let is_valid = !req_id.is_empty()
+        && req_id.len() <= REQUEST_ID_MAX_LEN
+        && req_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'));
+
+    is_valid.then(|| req_id.to_string())
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ReqStamp {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        debug!("{:<12} - ReqStamp", "EXTRACTOR");
+
+        parts
+            .extensions
+            .get::<ReqStamp>()
+            .cloned()
+            .ok_or(Error::ReqStampNotInReqExt)
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_req_id_from_headers() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            ("0f6c1a8e-6e3b-4bd5-9a39-1d2f3c4b5a69", true),
+            ("client-42.retry:1", true),
+            ("", false),
+            ("has space", false),
+            ("line\r\nbreak", false),
+            (&"x".repeat(REQUEST_ID_MAX_LEN + 1), false),
+        ];
+
+        for (fx_req_id, honored) in fx_cases {
+            let mut fx_headers = HeaderMap::new();
+            if let Ok(value) = HeaderValue::from_str(fx_req_id) {
+                fx_headers.insert(REQUEST_ID_HEADER, value);
+            }
+
+            // -- Exec
+            let req_id = req_id_from_headers(&fx_headers);
+
+            // -- Check
+            assert_eq!(req_id.is_some(), honored, "for {fx_req_id:?}");
+        }
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -1,24 +1,35 @@
 use tower_cookies::{Cookie, Cookies};
 use uuid::Uuid;
+use crate::config::config;
 use crate::token::generate_web_token;
 
 pub use self::error::ClientError;
 pub use self::error::{Error, Result};
 
 pub(crate) mod error;
+pub mod extract;
 pub mod routes_login;
+pub mod routes_events;
+pub mod routes_health;
+pub mod routes_metrics;
+pub mod routes_tasks;
 pub mod routes_tickets;
 pub mod routes_static;
 pub mod middlewares;
+pub mod i18n;
 pub mod rpc;
+pub mod validation;
 
 pub const AUTH_TOKEN: &str = "auth-token";
 
 fn set_token_cookie(cookies: &Cookies, user: &str, salt: Uuid) -> Result<()> {
     let token = generate_web_token(user, salt)?;
 
+    let config = config();
     let mut cookie = Cookie::new(AUTH_TOKEN, token.to_string());
     cookie.set_http_only(true);
+    cookie.set_secure(config.COOKIE_SECURE);
+    cookie.set_same_site(config.COOKIE_SAME_SITE);
     cookie.set_path("/");
     cookies.add(cookie);
 
@@ -0,0 +1,133 @@
+use std::convert::Infallible;
+use axum::extract::State;
+use axum::response::sse::{Event as SseEvent, KeepAlive, Sse};
+use axum::routing::get;
+use axum::Router;
+use futures_util::stream::{self, Stream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::broadcast::Receiver;
+use tracing::log::{debug, warn};
+
+use tower_cookies::Cookies;
+
+use crate::ctx::Ctx;
+use crate::event::EventEnvelope;
+use crate::model::DbContext;
+use crate::token::Token;
+use crate::web;
+use crate::web::middlewares::auth::{cookie_token, ctx_from_token};
+
+pub fn routes(db_context: DbContext) -> Router {
+    Router::new()
+        .route("/events", get(stream_events))
+        .with_state(db_context)
+}
+
+/// The `Ctx` gates the opening, its token is then checked again for every
+/// event, see `event_stream`.
+async fn stream_events(
+    State(db_context): State<DbContext>,
+    _ctx: Ctx,
+    cookies: Cookies,
+) -> web::Result<Sse<impl Stream<Item = Result<SseEvent, Infallible>>>> {
+    debug!("{:<12} - stream_events", "HANDLER");
+
+    let token = cookie_token(&cookies)?;
+    let rx = db_context.events().subscribe();
+
+    Ok(Sse::new(event_stream(rx, db_context, token)).keep_alive(KeepAlive::default()))
+}
+
+/// Ends when the bus is closed, or once the token expires, is revoked or the
+/// user is disabled. Skips the events the user may not see.
+fn event_stream(
+    rx: Receiver<EventEnvelope>,
+    db_context: DbContext,
+    token: Token,
+) -> impl Stream<Item = Result<SseEvent, Infallible>> {
+    stream::unfold((rx, db_context, token), |(mut rx, db_context, token)| async move {
+        loop {
+            let envelope = match rx.recv().await {
+                Ok(envelope) => envelope,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!("{:<12} - subscriber lagged, {skipped} event(s) skipped", "SSE");
+                    continue;
+                }
+                Err(RecvError::Closed) => return None,
+            };
+
+            let ctx = match ctx_from_token(&db_context, &token).await {
+                Ok(ctx) => ctx,
+                Err(ex) => {
+                    debug!("{:<12} - sse session ended - {ex:?}", "SSE");
+                    return None;
+                }
+            };
+
+            if envelope.event.is_visible_to(&ctx) {
+                let name = envelope.event.name();
+                let sse_event = SseEvent::default()
+                    .id(envelope.id.to_string())
+                    .event(name)
+                    .json_data(&envelope)
+                    .unwrap_or_else(|_| SseEvent::default().event(name));
+
+                return Some((Ok(sse_event), (rx, db_context, token)));
+            }
+        }
+    })
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use crate::event::Event;
+    use crate::model::task::Task;
+    use crate::model::user::{UserForAuth, UserForCreate, UserRepository};
+    use crate::token::generate_web_token;
+    use anyhow::Result;
+    use futures_util::StreamExt;
+    use serial_test::serial;
+    use tokio::sync::broadcast;
+    use uuid::Uuid;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_event_stream_ends_user_disabled() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::root_ctx();
+        let fx_user_c = UserForCreate {
+            username: "test_event_stream_ends_user_disabled".to_string(),
+            pwd_clear: "welcome-01".to_string(),
+        };
+        let id = UserRepository::create(&ctx, &db_context, fx_user_c).await?;
+        let user: UserForAuth = UserRepository::get(&ctx, &db_context, id).await?;
+        let fx_token = generate_web_token(&user.username, user.token_salt)?;
+        let fx_envelope = || EventEnvelope {
+            id: Uuid::new_v4(),
+            event: Event::TaskCreated {
+                actor_id: id,
+                task: Task { id: 1000, title: "test_event_stream_ends_user_disabled".to_string() },
+            },
+        };
+        let (tx, rx) = broadcast::channel(8);
+        let mut stream = std::pin::pin!(event_stream(rx, db_context.clone(), fx_token));
+
+        // -- Exec
+        tx.send(fx_envelope())?;
+        let first = stream.next().await;
+        UserRepository::set_disabled(&ctx, &db_context, id, true).await?;
+        tx.send(fx_envelope())?;
+        let second = stream.next().await;
+
+        // -- Check
+        assert!(first.is_some(), "Should have sent the event");
+        assert!(second.is_none(), "Should have ended the stream");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::log::{debug, warn};
+
+use crate::model::{migration, DbContext};
+use crate::utils::task_utils::WorkerHandle;
+
+/// A check taking longer fails, the orchestrator should not wait on a stuck pool.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Merged outside of the auth and response mapping layers, probes have no cookie.
+pub fn routes(db_context: DbContext, workers: Vec<WorkerHandle>) -> Router {
+    Router::new()
+        .route("/health/live", get(health_live))
+        .route("/health/ready", get(health_ready))
+        .with_state(HealthState { db_context, workers: workers.into() })
+}
+
+#[derive(Clone)]
+struct HealthState {
+    db_context: DbContext,
+    workers: Arc<[WorkerHandle]>,
+}
+
+// region: -- Types
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    Fail,
+}
+
+#[derive(Debug, Serialize)]
+struct Check {
+    status: CheckStatus,
+    latency_ms: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Readiness {
+    status: CheckStatus,
+    checks: BTreeMap<&'static str, Check>,
+}
+
+// endregion: -- Types
+
+async fn health_live() -> Json<Value> {
+    Json(json!({ "status": CheckStatus::Ok }))
+}
+
+async fn health_ready(State(state): State<HealthState>) -> (StatusCode, Json<Readiness>) {
+    debug!("{:<12} - health_ready", "HANDLER");
+
+    let readiness = readiness(&state.db_context, &state.workers).await;
+    let status_code = match readiness.status {
+        CheckStatus::Ok => StatusCode::OK,
+        CheckStatus::Fail => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status_code, Json(readiness))
+}
+
+async fn readiness(db_context: &DbContext, workers: &[WorkerHandle]) -> Readiness {
+    let (database, migrations) = tokio::join!(
+        run_check("database", async { db_context.ping().await.map_err(|ex| ex.to_string()) }),
+        run_check("migrations", async {
+            match migration::is_up_to_date(db_context).await {
+                Ok(true) => Ok(()),
+                Ok(false) => Err("pending or modified migrations".to_string()),
+                Err(ex) => Err(ex.to_string()),
+            }
+        }),
+    );
+    let workers = run_check("workers", async {
+        let stopped: Vec<&str> = workers.iter().filter(|w| !w.is_running()).map(|w| w.name).collect();
+        if stopped.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("stopped: {}", stopped.join(", ")))
+        }
+    })
+    .await;
+
+    let checks = BTreeMap::from([
+        ("database", database),
+        ("migrations", migrations),
+        ("workers", workers),
+    ]);
+    let status = if checks.values().all(|check| check.status == CheckStatus::Ok) {
+        CheckStatus::Ok
+    } else {
+        CheckStatus::Fail
+    };
+
+    Readiness { status, checks }
+}
+
+/// The error is only logged, the probe is not authenticated.
+async fn run_check(name: &str, check: impl Future<Output = Result<(), String>>) -> Check {
+    let start = Instant::now();
+    let res = tokio::time::timeout(CHECK_TIMEOUT, check)
+        .await
+        .unwrap_or_else(|_| Err(format!("timed out after {CHECK_TIMEOUT:?}")));
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.;
+
+    match res {
+        Ok(()) => Check { status: CheckStatus::Ok, latency_ms },
+        Err(error) => {
+            warn!("{:<12} - readiness check {name} failed: {error}", "HEALTH");
+            Check { status: CheckStatus::Fail, latency_ms }
+        }
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use crate::utils::task_utils::spawn_worker;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_readiness_err_worker_stopped() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let fx_running = spawn_worker("fx_running", std::future::pending());
+        let fx_stopped = spawn_worker("fx_stopped", async {});
+        tokio::task::yield_now().await;
+
+        // -- Exec
+        let ready = readiness(&db_context, std::slice::from_ref(&fx_running)).await;
+        let not_ready = readiness(&db_context, &[fx_running, fx_stopped]).await;
+
+        // -- Check
+        assert_eq!(ready.status, CheckStatus::Ok, "Should be ready: {ready:?}");
+        assert_eq!(not_ready.status, CheckStatus::Fail);
+        assert_eq!(not_ready.checks["database"].status, CheckStatus::Ok);
+        assert_eq!(not_ready.checks["workers"].status, CheckStatus::Fail);
+        let body = serde_json::to_value(&not_ready.checks["workers"])?;
+        assert_eq!(body.as_object().map(|o| o.len()), Some(2), "Should only have the status and latency: {body}");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
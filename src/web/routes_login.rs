@@ -1,6 +1,6 @@
 use axum::extract::State;
 use axum::routing::post;
-use axum::{Json, Router};
+use axum::{middleware, Json, Router};
 use serde::Deserialize;
 use serde_json::{json, Value};
 use tower_cookies::{Cookie, Cookies};
@@ -8,16 +8,20 @@ use tracing::log::debug;
 
 use crate::pwd::{self, ContentToHash};
 use crate::ctx::Ctx;
+use crate::model::audit::{AuditAction, AuditEventForCreate, AuditRepository};
 use crate::model::user::{UserForLogin, UserRepository};
 use crate::model::DbContext;
 use crate::web;
+use crate::web::middlewares::rate_limit::mw_rate_limit_login;
+use crate::web::middlewares::stamp::ReqStamp;
+use crate::web::extract::JsonBody;
 use crate::web::AUTH_TOKEN;
 
 use super::{Error, Result};
 
 pub fn routes(db_context: DbContext) -> Router {
     Router::new()
-        .route("/api/login", post(api_login))
+        .route("/api/login", post(api_login).route_layer(middleware::from_fn(mw_rate_limit_login)))
         .route("/api/logout", post(api_logout))
         .with_state(db_context)
 }
@@ -30,11 +34,39 @@ struct LoginPayload {
 
 async fn api_login(
     State(db_context): State<DbContext>,
+    req_stamp: ReqStamp,
     cookies: Cookies,
-    Json(payload): Json<LoginPayload>) -> Result<Json<Value>>
+    JsonBody(payload): JsonBody<LoginPayload>) -> Result<Json<Value>>
 {
     debug!("{:<12} - api_login", "HANDLER");
 
+    let username = payload.username.clone();
+    let res = login(&db_context, payload).await;
+
+    // Failed or not, the attempt is audited.
+    let ctx = Ctx::root_ctx().with_req_id(req_stamp.id);
+    let event = match &res {
+        Ok(user) => AuditEventForCreate::new(&ctx, AuditAction::LoginSuccess).actor(Some(user.id)),
+        Err(ex) => AuditEventForCreate::new(&ctx, AuditAction::LoginFailure)
+            .actor(ex.login_user_id())
+            .detail(json!({ "username": username, "reason": ex.as_ref() })),
+    };
+    AuditRepository::record(&ctx, &db_context, event).await?;
+    let user = res?;
+
+    web::set_token_cookie(&cookies, &user.username, user.token_salt)?;
+
+    let body = Json(json!({
+        "result": {
+            "succes": true
+        }
+    }));
+
+    Ok(body)
+}
+
+/// Checks the credentials, returns the user to log in.
+async fn login(db_context: &DbContext, payload: LoginPayload) -> Result<UserForLogin> {
     let LoginPayload {
         username,
         password: pwd_clear,
@@ -42,44 +74,56 @@ async fn api_login(
 
     let root_ctx = Ctx::root_ctx();
 
-    let user: UserForLogin = UserRepository::first_by_username(&root_ctx, &db_context, &username)
+    let user: UserForLogin = UserRepository::first_by_username(&root_ctx, db_context, &username)
         .await?
         .ok_or(Error::LoginFailUserNotFound)?;
     let user_id = user.id;
-    let Some(pwd) = user.pwd else {
+    if user.disabled {
+        return Err(Error::LoginFailUserDisabled { user_id });
+    }
+    let Some(pwd) = &user.pwd else {
         return Err(Error::LoginFailUserHasNoPassword)
     };
 
     pwd::validate_pwd(
         &ContentToHash {
             salt: user.pwd_salt,
-            content: pwd_clear.clone(),
+            content: pwd_clear,
         },
-        &pwd,
-    ).map_err(|_| Error::LoginFailPasswordNotMatching { user_id });
-
-    web::set_token_cookie(&cookies, &user.username, user.token_salt)?;
-
-    let body = Json(json!({
-        "result": {
-            "succes": true
-        }
-    }));
+        pwd,
+    ).map_err(|_| Error::LoginFailPasswordNotMatching { user_id })?;
 
-    Ok(body)
+    Ok(user)
 }
 
 #[derive(Debug, Deserialize)]
 struct LogoutPayload {
     logout: bool,
+    /// Also revokes the other tokens of the user, ending its other sessions.
+    #[serde(default)]
+    everywhere: bool,
 }
 
-async fn api_logout(cookies: Cookies, Json(payload): Json<LogoutPayload>) -> Result<Json<Value>> {
+async fn api_logout(
+    State(db_context): State<DbContext>,
+    ctx: Option<Ctx>,
+    cookies: Cookies,
+    JsonBody(payload): JsonBody<LogoutPayload>,
+) -> Result<Json<Value>> {
     debug!("{:<12} - api_logout", "HANDLER");
     let should_logoff = payload.logout;
 
     if should_logoff {
         web::remove_token_cookie(&cookies);
+
+        // Without a valid token, there is no one to log out.
+        if let Some(ctx) = ctx {
+            if payload.everywhere {
+                UserRepository::revoke_tokens(&ctx, &db_context, ctx.user_id()).await?;
+            }
+            let event = AuditEventForCreate::new(&ctx, AuditAction::Logout);
+            AuditRepository::record(&ctx, &db_context, event).await?;
+        }
     }
 
     let body = Json(json!({
@@ -0,0 +1,27 @@
+use axum::extract::State;
+use axum::http::header::CONTENT_TYPE;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use tracing::log::debug;
+
+use crate::metrics::metrics;
+use crate::model::DbContext;
+
+/// Merged outside of the auth layers, like the health probes, for the scraper.
+pub fn routes(db_context: DbContext) -> Router {
+    Router::new()
+        .route("/metrics", get(render_metrics))
+        .with_state(db_context)
+}
+
+async fn render_metrics(State(db_context): State<DbContext>) -> impl IntoResponse {
+    debug!("{:<12} - render_metrics", "HANDLER");
+
+    let metrics = metrics();
+    let (size, idle) = db_context.pool_state();
+    metrics.db_pool_size.set(i64::from(size));
+    metrics.db_pool_idle.set(idle as i64);
+
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], metrics.render())
+}
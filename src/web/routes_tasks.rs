@@ -0,0 +1,92 @@
+use axum::extract::{Path, State};
+use axum::http::header::LOCATION;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use tracing::log::debug;
+
+use crate::ctx::Ctx;
+use crate::model::task::{Task, TaskForCreate, TaskForUpdate, TaskRepository};
+use crate::model::DbContext;
+use crate::web::Result;
+use crate::web::extract::JsonBody;
+use crate::web::validation::validate;
+
+pub fn routes(db_context: DbContext) -> Router {
+    Router::new()
+        .route("/tasks", get(list_tasks).post(create_task))
+        .route(
+            "/tasks/:id",
+            get(get_task).patch(update_task).delete(delete_task),
+        )
+        .with_state(db_context)
+}
+
+async fn create_task(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    JsonBody(task_c): JsonBody<TaskForCreate>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - create_task", "HANDLER");
+
+    validate(&task_c)?;
+
+    let id = TaskRepository::create(&ctx, &db_context, task_c).await?;
+    let task = TaskRepository::get(&ctx, &db_context, id).await?;
+
+    let location = format!("/api/tasks/{id}");
+
+    Ok((StatusCode::CREATED, [(LOCATION, location)], Json(task)))
+}
+
+async fn list_tasks(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+) -> Result<Json<Vec<Task>>> {
+    debug!("{:<12} - list_tasks", "HANDLER");
+
+    let tasks = TaskRepository::list(&ctx, &db_context).await?;
+
+    Ok(Json(tasks))
+}
+
+async fn get_task(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    Path(id): Path<i64>,
+) -> Result<Json<Task>> {
+    debug!("{:<12} - get_task - {id}", "HANDLER");
+
+    let task = TaskRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(Json(task))
+}
+
+async fn update_task(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    Path(id): Path<i64>,
+    JsonBody(task_u): JsonBody<TaskForUpdate>,
+) -> Result<Json<Task>> {
+    debug!("{:<12} - update_task - {id}", "HANDLER");
+
+    validate(&task_u)?;
+
+    TaskRepository::update(&ctx, &db_context, id, task_u).await?;
+    let task = TaskRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(Json(task))
+}
+
+async fn delete_task(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    Path(id): Path<i64>,
+) -> Result<StatusCode> {
+    debug!("{:<12} - delete_task - {id}", "HANDLER");
+
+    TaskRepository::delete(&ctx, &db_context, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
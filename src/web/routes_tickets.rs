@@ -1,59 +1,92 @@
-// use axum::extract::{FromRef, Path, State};
-// use axum::{Json, Router};
-// use axum::routing::{delete, get, post};
-// use tracing::log::debug;
-// use crate::ctx::Ctx;
-//
-// use crate::Error;
-// use crate::model::ticket::TicketRepository;
-// use crate::model::ticket::Ticket;
-// use crate::model::ticket::TicketForCreate;
-//
-// #[derive(Clone, FromRef)]
-// struct AppState {
-//     mc: TicketRepository,
-// }
-//
-// pub fn routes(mc: TicketRepository) -> Router {
-//     let app_state = AppState { mc };
-//     Router::new()
-//         .route("/tickets", post(create_ticket))
-//         .route("/tickets", get(list_tickets))
-//         .route("/tickets/:id", delete(delete_ticket))
-//         .with_state(app_state)
-// }
-//
-// async fn create_ticket(
-//     State(mc): State<TicketRepository>,
-//     ctx: Ctx,
-//     Json(ticket_for_create): Json<TicketForCreate>, // Recover data from body
-// ) -> Result<Json<Ticket>, Error> {
-//     debug!("{:<12} - create_ticket", "HANDLER");
-//
-//     let ticket = mc.create_ticket(ctx, ticket_for_create).await?;
-//
-//     Ok(Json(ticket))
-// }
-//
-// async fn list_tickets(
-//     State(mc): State<TicketRepository>,
-//     ctx: Ctx,
-// ) -> Result<Json<Vec<Ticket>>, Error> {
-//     debug!("{:<12} - list_tickets", "HANDLER");
-//
-//     let tickets = mc.list_tickets(ctx).await?;
-//
-//     Ok(Json(tickets))
-// }
-//
-// async fn delete_ticket(
-//     State(mc): State<TicketRepository>,
-//     ctx: Ctx,
-//     Path(id): Path<u64>, // recover data from path
-// ) -> Result<Json<Ticket>, Error> {
-//     debug!("{:<12} - delete_ticket", "HANDLER");
-//
-//     let ticket = mc.delete_ticket(ctx, id).await?;
-//
-//     Ok(Json(ticket))
-// }
\ No newline at end of file
+use axum::extract::{Path, State};
+use axum::http::header::LOCATION;
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use tracing::log::debug;
+
+use crate::ctx::Ctx;
+use crate::model::ticket::{Ticket, TicketForCreate, TicketForUpdate, TicketRepository};
+use crate::model::DbContext;
+use crate::web::Result;
+use crate::web::extract::JsonBody;
+use crate::web::validation::validate;
+
+pub fn routes(db_context: DbContext) -> Router {
+    Router::new()
+        .route("/tickets", get(list_tickets).post(create_ticket))
+        .route(
+            "/tickets/:id",
+            get(get_ticket).patch(update_ticket).delete(delete_ticket),
+        )
+        .with_state(db_context)
+}
+
+async fn create_ticket(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    JsonBody(ticket_c): JsonBody<TicketForCreate>,
+) -> Result<impl IntoResponse> {
+    debug!("{:<12} - create_ticket", "HANDLER");
+
+    validate(&ticket_c)?;
+
+    let id = TicketRepository::create(&ctx, &db_context, ticket_c).await?;
+    let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+
+    let location = format!("/api/tickets/{id}");
+
+    Ok((StatusCode::CREATED, [(LOCATION, location)], Json(ticket)))
+}
+
+async fn list_tickets(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+) -> Result<Json<Vec<Ticket>>> {
+    debug!("{:<12} - list_tickets", "HANDLER");
+
+    let tickets = TicketRepository::list(&ctx, &db_context).await?;
+
+    Ok(Json(tickets))
+}
+
+async fn get_ticket(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    Path(id): Path<i64>,
+) -> Result<Json<Ticket>> {
+    debug!("{:<12} - get_ticket - {id}", "HANDLER");
+
+    let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(Json(ticket))
+}
+
+async fn update_ticket(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    Path(id): Path<i64>,
+    JsonBody(ticket_u): JsonBody<TicketForUpdate>,
+) -> Result<Json<Ticket>> {
+    debug!("{:<12} - update_ticket - {id}", "HANDLER");
+
+    validate(&ticket_u)?;
+
+    TicketRepository::update(&ctx, &db_context, id, ticket_u).await?;
+    let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(Json(ticket))
+}
+
+async fn delete_ticket(
+    State(db_context): State<DbContext>,
+    ctx: Ctx,
+    Path(id): Path<i64>,
+) -> Result<StatusCode> {
+    debug!("{:<12} - delete_ticket - {id}", "HANDLER");
+
+    TicketRepository::delete(&ctx, &db_context, id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,43 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use validator::Validate;
+use crate::ctx::Ctx;
+use crate::model::audit::{AuditEvent, AuditFilter, AuditRepository};
+use crate::model::DbContext;
+use crate::web::Result;
+use crate::web::rpc::require_admin;
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct ParamsAuditFilter {
+    pub actor_id: Option<i64>,
+    #[validate(length(max = 64))]
+    pub entity: Option<String>,
+    /// RFC 3339, inclusive.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schemars(with = "Option<String>")]
+    pub from: Option<OffsetDateTime>,
+    /// RFC 3339, exclusive.
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    #[schemars(with = "Option<String>")]
+    pub to: Option<OffsetDateTime>,
+    /// The last id of the previous page, for the next one.
+    #[validate(range(min = 1))]
+    pub before_id: Option<i64>,
+    /// Page size, 200 by default and at most.
+    #[validate(range(min = 1, max = 200))]
+    pub limit: Option<u64>,
+}
+
+/// Admin only.
+pub async fn list_audit_events(ctx: Ctx, db_context: DbContext, params: ParamsAuditFilter)
+    -> Result<Vec<AuditEvent>> {
+    require_admin(&ctx)?;
+
+    let ParamsAuditFilter { actor_id, entity, from, to, before_id, limit } = params;
+    let filter = AuditFilter { actor_id, entity, from, to, before_id, limit };
+
+    let events = AuditRepository::list(&ctx, &db_context, filter).await?;
+
+    Ok(events)
+}
@@ -0,0 +1,68 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+use validator::Validate;
+use crate::ctx::Ctx;
+use crate::model::DbContext;
+use crate::model::job::{Job, JobRepository, JobStatus};
+use crate::web::Result;
+use crate::web::rpc::{require_admin, ParamsId};
+
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct ParamsJobFilter {
+    pub status: Option<JobStatus>,
+}
+
+/// Admin only, the payloads are those of every user.
+pub async fn list_job(ctx: Ctx, db_context: DbContext, params: ParamsJobFilter)
+    -> Result<Vec<Job>> {
+    require_admin(&ctx)?;
+
+    let ParamsJobFilter { status } = params;
+
+    let jobs = JobRepository::list(&ctx, &db_context, status).await?;
+
+    Ok(jobs)
+}
+
+/// Admin only.
+pub async fn retry_job(ctx: Ctx, db_context: DbContext, params: ParamsId) -> Result<Job> {
+    require_admin(&ctx)?;
+
+    let ParamsId { id } = params;
+
+    JobRepository::retry(&ctx, &db_context, id).await?;
+    let job = JobRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(job)
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use crate::web::Error;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_job_rpc_err_not_admin() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let fx_ctx = Ctx::new(1000)?;
+
+        // -- Exec
+        let list_res = list_job(fx_ctx.clone(), db_context.clone(), ParamsJobFilter { status: None }).await;
+        let retry_res = retry_job(fx_ctx, db_context.clone(), ParamsId { id: 1000 }).await;
+        let admin_res = list_job(Ctx::root_ctx(), db_context, ParamsJobFilter { status: None }).await;
+
+        // -- Check
+        assert!(matches!(list_res, Err(Error::AccessDeniedNotAdmin { user_id: 1000 })));
+        assert!(matches!(retry_res, Err(Error::AccessDeniedNotAdmin { user_id: 1000 })));
+        assert!(admin_res.is_ok());
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
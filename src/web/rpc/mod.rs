@@ -1,18 +1,41 @@
 use axum::extract::State;
-use axum::{Json, Router};
+use axum::{middleware, Json, Router};
 use axum::response::{IntoResponse, Response};
 use axum::routing::{get, post};
+use schemars::SchemaGenerator;
 use serde::Deserialize;
 use serde_json::{from_value, json, to_value, Value};
 use log::debug;
 use crate::ctx::Ctx;
 use crate::model::DbContext;
-use crate::web::{Error, Result};
+use crate::model::audit::AuditEvent;
+use crate::model::job::Job;
+use crate::model::task::{Task, TaskForCreate, TaskForUpdate};
+use crate::model::ticket::{Ticket, TicketForCreate, TicketForUpdate};
+use crate::model::webhook::{Webhook, WebhookDelivery, WebhookForCreate, WebhookForUpdate};
+use crate::web::{ClientError, Error, Result};
+use crate::web::i18n::Lang;
+use crate::web::middlewares::rate_limit::{check_rpc_method, mw_rate_limit_rpc};
+use crate::web::validation::validate;
+use crate::web::rpc::audit_rpc::{list_audit_events, ParamsAuditFilter};
+use crate::web::rpc::job_rpc::{list_job, retry_job, ParamsJobFilter};
+use crate::web::rpc::openrpc::{openrpc_document, rpc_discover};
 use crate::web::rpc::task_rpc::{create_task, delete_task, get_task, list_task, update_task};
-use params::*;
-
-mod params;
+use crate::web::rpc::ticket_rpc::{create_ticket, delete_ticket, get_ticket, list_ticket, update_ticket};
+use crate::web::rpc::webhook_rpc::{
+    create_webhook, delete_webhook, list_webhook, list_webhook_delivery, update_webhook,
+};
+use crate::web::rpc::ws::ws_handler;
+pub(crate) use params::*;
+
+mod audit_rpc;
+mod job_rpc;
+mod openrpc;
+pub(crate) mod params;
 mod task_rpc;
+mod ticket_rpc;
+mod webhook_rpc;
+mod ws;
 
 
 #[derive(Deserialize)]
@@ -24,7 +47,9 @@ struct RpcRequest {
 
 pub fn routes(db_context: DbContext) -> Router {
     Router::new()
-        .route("/rpc", post(rpc_handler))
+        .route("/rpc", post(rpc_handler).route_layer(middleware::from_fn(mw_rate_limit_rpc)))
+        .route("/rpc/schema", get(rpc_schema_handler))
+        .route("/ws", get(ws_handler))
         .with_state(db_context)
 }
 
@@ -32,7 +57,9 @@ async fn rpc_handler(State(db_context): State<DbContext>, ctx: Ctx, Json(rpc_req
     let rpc_info = RpcInfo {
         id: rpc_req.id.clone(),
         method: rpc_req.method.clone(),
+        params: rpc_req.params.clone(),
     };
+    tracing::Span::current().record("rpc_method", rpc_info.method.as_str());
 
     let mut response = _rpc_handler(ctx, db_context, rpc_req).await.into_response();
 
@@ -41,10 +68,18 @@ async fn rpc_handler(State(db_context): State<DbContext>, ctx: Ctx, Json(rpc_req
     response
 }
 
+async fn rpc_schema_handler() -> Json<Value> {
+    debug!("{:<12} - rpc_schema_handler", "HANDLER");
+
+    Json(openrpc_document().clone())
+}
+
 #[derive(Debug, Clone)]
 pub struct RpcInfo {
     pub id: Option<Value>,
     pub method: String,
+    /// Unredacted, only logged through `log_request`.
+    pub params: Option<Value>,
 }
 
 macro_rules! exec_rpc_fn {
@@ -54,18 +89,107 @@ macro_rules! exec_rpc_fn {
     };
 
     // with params
-    ($rpc_fn:expr, $ctx:expr, $db_context:expr, $rpc_params: expr) => {{
+    ($rpc_fn:expr, $ctx:expr, $db_context:expr, $rpc_params: expr, $params_ty:ty) => {{
         let rpc_fn_name = stringify!($rpc_fn);
         let params = $rpc_params.ok_or(Error::RpcMissingParams {
             rpc_method: rpc_fn_name.to_string()
         })?;
-        let params = from_value(params).map_err(|_| Error::RpcFailJsonParams {
+        let params: $params_ty = from_value(params).map_err(|_| Error::RpcFailJsonParams {
             rpc_method: rpc_fn_name.to_string()
         })?;
+        validate(&params)?;
         $rpc_fn($ctx, $db_context, params).await.map(to_value)??
         }};
 }
 
+/// Declares the RPC surface once, and generates from it both the method
+/// dispatch and the OpenRPC method descriptors, so they cannot drift apart.
+macro_rules! rpc_methods {
+    ($($method:literal => $rpc_fn:ident($($params_ty:ty)?) -> $result_ty:ty),* $(,)?) => {
+        async fn rpc_dispatch(
+            ctx: Ctx,
+            db_context: DbContext,
+            rpc_method: String,
+            rpc_params: Option<Value>,
+        ) -> Result<Value> {
+            let result_json: Value = match rpc_method.as_str() {
+                $($method => exec_rpc_fn!($rpc_fn, ctx, db_context $(, rpc_params, $params_ty)?),)*
+                _ => return Err(Error::RpcMethodUnknown(rpc_method))
+            };
+
+            Ok(result_json)
+        }
+
+        fn rpc_method_specs(gen: &mut SchemaGenerator) -> Vec<Value> {
+            vec![$({
+                let params: Vec<Value> = Vec::new();
+                $(let params = openrpc::params_spec::<$params_ty>(gen);)?
+                openrpc::method_spec::<$result_ty>(gen, $method, params)
+            }),*]
+        }
+    };
+}
+
+rpc_methods! {
+    "rpc.discover" => rpc_discover() -> Value,
+
+    "create_task" => create_task(ParamsForCreate<TaskForCreate>) -> Task,
+    "list_task" => list_task() -> Vec<Task>,
+    "get_task" => get_task(ParamsId) -> Task,
+    "update_task" => update_task(ParamsForUpdate<TaskForUpdate>) -> Task,
+    "delete_task" => delete_task(ParamsId) -> Task,
+
+    "create_ticket" => create_ticket(ParamsForCreate<TicketForCreate>) -> Ticket,
+    "list_ticket" => list_ticket() -> Vec<Ticket>,
+    "get_ticket" => get_ticket(ParamsId) -> Ticket,
+    "update_ticket" => update_ticket(ParamsForUpdate<TicketForUpdate>) -> Ticket,
+    "delete_ticket" => delete_ticket(ParamsId) -> Ticket,
+
+    "create_webhook" => create_webhook(ParamsForCreate<WebhookForCreate>) -> Webhook,
+    "list_webhook" => list_webhook() -> Vec<Webhook>,
+    "update_webhook" => update_webhook(ParamsForUpdate<WebhookForUpdate>) -> Webhook,
+    "delete_webhook" => delete_webhook(ParamsId) -> Webhook,
+    "list_webhook_delivery" => list_webhook_delivery(ParamsId) -> Vec<WebhookDelivery>,
+
+    "list_job" => list_job(ParamsJobFilter) -> Vec<Job>,
+    "retry_job" => retry_job(ParamsId) -> Job,
+
+    "list_audit_events" => list_audit_events(ParamsAuditFilter) -> Vec<AuditEvent>,
+}
+
+/// For the RPC methods restricted to the admins.
+fn require_admin(ctx: &Ctx) -> Result<()> {
+    if !ctx.is_admin() {
+        return Err(Error::AccessDeniedNotAdmin { user_id: ctx.user_id() });
+    }
+
+    Ok(())
+}
+
+pub(crate) fn rpc_error_body(
+    rpc_info: Option<&RpcInfo>,
+    client_error: &ClientError,
+    req_id: &str,
+    lang: Lang,
+) -> Value {
+    let description = client_error.message(lang);
+    let client_error = to_value(client_error).ok();
+    let message = client_error.as_ref().and_then(|v| v.get("message"));
+    let detail = client_error.as_ref().and_then(|v| v.get("detail"));
+
+    json!({
+        "id": rpc_info.as_ref().map(|rpc| rpc.id.clone()),
+        "error": {
+            "message": message,
+            "data": {
+                "req_uuid": req_id,
+                "description": description,
+                "detail": detail
+            }
+        }
+    })
+}
+
 async fn _rpc_handler(ctx: Ctx, db_context: DbContext, request: RpcRequest) -> Result<Json<Value>> {
     let RpcRequest {
         id: rpc_id,
@@ -75,14 +199,8 @@ async fn _rpc_handler(ctx: Ctx, db_context: DbContext, request: RpcRequest) -> R
 
     debug!("{:<12} - _rpc_handler - method: {rpc_method}", "HANDLER");
 
-    let result_json: Value = match rpc_method.as_str() {
-        "create_task" => exec_rpc_fn!(create_task, ctx, db_context, rpc_params),
-        "list_task" => exec_rpc_fn!(list_task, ctx, db_context),
-        "get_task" => exec_rpc_fn!(get_task, ctx, db_context, rpc_params),
-        "update_task" => exec_rpc_fn!(update_task, ctx, db_context, rpc_params),
-        "delete_task" => exec_rpc_fn!(delete_task, ctx, db_context, rpc_params),
-        _ => return Err(Error::RpcMethodUnknown(rpc_method))
-    };
+    check_rpc_method(&ctx, &rpc_method).await?;
+    let result_json = rpc_dispatch(ctx, db_context, rpc_method, rpc_params).await?;
 
     let body_response = json!({
         "id": rpc_id,
@@ -0,0 +1,110 @@
+use std::sync::OnceLock;
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, SchemaGenerator};
+use serde_json::{json, Value};
+use crate::ctx::Ctx;
+use crate::model::DbContext;
+use crate::web::Result;
+use crate::web::rpc::rpc_method_specs;
+
+const OPENRPC_VERSION: &str = "1.2.6";
+const SCHEMAS_PATH: &str = "/components/schemas";
+
+pub fn openrpc_document() -> &'static Value {
+    static INSTANCE: OnceLock<Value> = OnceLock::new();
+
+    INSTANCE.get_or_init(build_openrpc_document)
+}
+
+pub async fn rpc_discover(_ctx: Ctx, _db_context: DbContext) -> Result<Value> {
+    Ok(openrpc_document().clone())
+}
+
+fn build_openrpc_document() -> Value {
+    let mut gen = SchemaSettings::draft07()
+        .with(|settings| settings.definitions_path = SCHEMAS_PATH.into())
+        .into_generator();
+
+    let methods = rpc_method_specs(&mut gen);
+    let schemas = gen.take_definitions(true);
+
+    json!({
+        "openrpc": OPENRPC_VERSION,
+        "info": {
+            "title": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "methods": methods,
+        "components": {
+            "schemas": schemas,
+        }
+    })
+}
+
+/// Params are passed by-name, so each property of the params type
+/// becomes its own content descriptor.
+pub fn params_spec<P: JsonSchema>(gen: &mut SchemaGenerator) -> Vec<Value> {
+    let schema = P::json_schema(gen);
+    let Some(properties) = schema.get("properties").and_then(Value::as_object) else {
+        return Vec::new();
+    };
+    let required = schema.get("required").and_then(Value::as_array);
+
+    properties
+        .iter()
+        .map(|(name, schema)| json!({
+            "name": name,
+            "required": required.is_some_and(|r| r.iter().any(|n| n == name)),
+            "schema": schema,
+        }))
+        .collect()
+}
+
+pub fn method_spec<R: JsonSchema>(
+    gen: &mut SchemaGenerator,
+    name: &str,
+    params: Vec<Value>,
+) -> Value {
+    json!({
+        "name": name,
+        "paramStructure": "by-name",
+        "params": params,
+        "result": {
+            "name": "result",
+            "schema": gen.subschema_for::<R>(),
+        }
+    })
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::{Context, Result};
+
+    #[test]
+    fn test_openrpc_document_task_methods_ok() -> Result<()> {
+        // -- Exec
+        let doc = openrpc_document();
+
+        // -- Check
+        let create_task = doc["methods"]
+            .as_array()
+            .context("methods should be an array")?
+            .iter()
+            .find(|m| m["name"] == "create_task")
+            .context("Should have 'create_task' method")?;
+        assert_eq!(create_task["params"][0]["name"], "data");
+        assert_eq!(
+            create_task["params"][0]["schema"]["$ref"],
+            "#/components/schemas/TaskForCreate"
+        );
+        assert_eq!(create_task["result"]["schema"]["$ref"], "#/components/schemas/Task");
+
+        let task_schema = &doc["components"]["schemas"]["Task"];
+        assert_eq!(task_schema["properties"]["title"]["type"], "string");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -1,17 +1,23 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
+use validator::Validate;
 
-#[derive(Deserialize)]
-pub struct ParamsForCreate<D> {
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct ParamsForCreate<D: Validate> {
+    #[validate(nested)]
     pub data: D,
 }
 
-#[derive(Deserialize)]
-pub struct ParamsForUpdate<D> {
+#[derive(Deserialize, JsonSchema, Validate)]
+pub struct ParamsForUpdate<D: Validate> {
+    #[validate(range(min = 1))]
     pub id: i64,
+    #[validate(nested)]
     pub data: D,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, JsonSchema, Validate)]
 pub struct ParamsId {
+    #[validate(range(min = 1))]
     pub id: i64,
 }
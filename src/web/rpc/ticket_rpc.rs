@@ -0,0 +1,49 @@
+use crate::ctx::Ctx;
+use crate::model::DbContext;
+use crate::model::ticket::{Ticket, TicketForCreate, TicketForUpdate, TicketRepository};
+use crate::web::Result;
+use crate::web::rpc::{ParamsForCreate, ParamsForUpdate, ParamsId};
+
+pub async fn create_ticket(ctx: Ctx, db_context: DbContext, params: ParamsForCreate<TicketForCreate>)
+    -> Result<Ticket> {
+    let ParamsForCreate { data } = params;
+
+    let id = TicketRepository::create(&ctx, &db_context, data).await?;
+    let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(ticket)
+}
+
+pub async fn get_ticket(ctx: Ctx, db_context: DbContext, params: ParamsId) -> Result<Ticket> {
+    let ParamsId { id } = params;
+
+    let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(ticket)
+}
+
+pub async fn list_ticket(ctx: Ctx, db_context: DbContext)
+    -> Result<Vec<Ticket>> {
+    let tickets = TicketRepository::list(&ctx, &db_context).await?;
+
+    Ok(tickets)
+}
+
+pub async fn update_ticket(ctx: Ctx, db_context: DbContext, params: ParamsForUpdate<TicketForUpdate>)
+    -> Result<Ticket> {
+    let ParamsForUpdate { id, data } = params;
+
+    TicketRepository::update(&ctx, &db_context, id, data).await?;
+    let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(ticket)
+}
+
+pub async fn delete_ticket(ctx: Ctx, db_context: DbContext, params: ParamsId) -> Result<Ticket> {
+    let ParamsId { id } = params;
+
+    let ticket = TicketRepository::get(&ctx, &db_context, id).await?;
+    TicketRepository::delete(&ctx, &db_context, id).await?;
+
+    Ok(ticket)
+}
@@ -0,0 +1,54 @@
+use crate::ctx::Ctx;
+use crate::model::DbContext;
+use crate::model::webhook::{
+    Webhook, WebhookDelivery, WebhookDeliveryRepository, WebhookForCreate, WebhookForUpdate,
+    WebhookRepository,
+};
+use crate::web::Result;
+use crate::web::rpc::{ParamsForCreate, ParamsForUpdate, ParamsId};
+
+pub async fn create_webhook(ctx: Ctx, db_context: DbContext, params: ParamsForCreate<WebhookForCreate>)
+    -> Result<Webhook> {
+    let ParamsForCreate { data } = params;
+
+    let id = WebhookRepository::create(&ctx, &db_context, data).await?;
+    let webhook = WebhookRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(webhook)
+}
+
+pub async fn list_webhook(ctx: Ctx, db_context: DbContext)
+    -> Result<Vec<Webhook>> {
+    let webhooks = WebhookRepository::list(&ctx, &db_context).await?;
+
+    Ok(webhooks)
+}
+
+pub async fn update_webhook(ctx: Ctx, db_context: DbContext, params: ParamsForUpdate<WebhookForUpdate>)
+    -> Result<Webhook> {
+    let ParamsForUpdate { id, data } = params;
+
+    WebhookRepository::update(&ctx, &db_context, id, data).await?;
+    let webhook = WebhookRepository::get(&ctx, &db_context, id).await?;
+
+    Ok(webhook)
+}
+
+pub async fn delete_webhook(ctx: Ctx, db_context: DbContext, params: ParamsId) -> Result<Webhook> {
+    let ParamsId { id } = params;
+
+    let webhook = WebhookRepository::get(&ctx, &db_context, id).await?;
+    WebhookRepository::delete(&ctx, &db_context, id).await?;
+
+    Ok(webhook)
+}
+
+/// `id` is the webhook id.
+pub async fn list_webhook_delivery(ctx: Ctx, db_context: DbContext, params: ParamsId)
+    -> Result<Vec<WebhookDelivery>> {
+    let ParamsId { id } = params;
+
+    let deliveries = WebhookDeliveryRepository::list_for_webhook(&ctx, &db_context, id).await?;
+
+    Ok(deliveries)
+}
@@ -0,0 +1,240 @@
+use axum::extract::ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade};
+use axum::extract::State;
+use axum::http::header::ACCEPT_LANGUAGE;
+use axum::http::{HeaderMap, Method, Uri};
+use axum::response::Response;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{from_str, json, Value};
+use tokio::sync::{mpsc, Semaphore};
+use tower_cookies::Cookies;
+use tracing::debug;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Instant;
+use uuid::Uuid;
+use crate::ctx::Ctx;
+use crate::log::{log_request, RequestLog};
+use crate::metrics::metrics;
+use crate::model::DbContext;
+use crate::token::Token;
+use crate::web::middlewares::auth::{cookie_token, ctx_from_token};
+use crate::web::{Error, Result};
+use crate::web::middlewares::rate_limit::{check_rpc_method, check_rpc_user};
+use crate::web::middlewares::stamp::ReqStamp;
+use crate::web::i18n::{Lang, LANG_COOKIE};
+use crate::web::rpc::{rpc_dispatch, rpc_error_body, RpcInfo, RpcRequest};
+
+const WS_PATH: &str = "/api/ws";
+const WS_OUTBOX_CAPACITY: usize = 64;
+/// Calls of a connection running at once. Past it, the next messages are
+/// not read until a call completes.
+const WS_MAX_IN_FLIGHT: usize = 16;
+
+/// The upgrade requires a `Ctx`. Its token, refreshed by `mw_ctx_resolver`,
+/// is checked again for every call, so the connection is closed once the
+/// token expires, is revoked or the user is disabled.
+pub async fn ws_handler(
+    State(db_context): State<DbContext>,
+    _ctx: Ctx,
+    req_stamp: ReqStamp,
+    headers: HeaderMap,
+    cookies: Cookies,
+    ws: WebSocketUpgrade,
+) -> Result<Response> {
+    debug!("{:<12} - ws_handler", "HANDLER");
+
+    let token = cookie_token(&cookies)?;
+
+    let lang = Lang::negotiate(
+        cookies.get(LANG_COOKIE).as_ref().map(|c| c.value()),
+        headers.get(ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()),
+    );
+
+    let client_ip = req_stamp.client_ip;
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, token, db_context, lang, client_ip)))
+}
+
+async fn handle_socket(
+    socket: WebSocket,
+    token: Token,
+    db_context: DbContext,
+    lang: Lang,
+    client_ip: Option<IpAddr>,
+) {
+    let (mut sender, mut receiver) = socket.split();
+    let (tx, mut rx) = mpsc::channel::<Message>(WS_OUTBOX_CAPACITY);
+
+    // -- Single writer, responses go out as soon as their call completes.
+    let writer = tokio::spawn(async move {
+        while let Some(message) = rx.recv().await {
+            if sender.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    // -- Each call runs on its own task, clients match responses by `id`.
+    let in_flight = Arc::new(Semaphore::new(WS_MAX_IN_FLIGHT));
+    while let Some(Ok(message)) = receiver.next().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        let ctx = match ctx_from_token(&db_context, &token).await {
+            Ok(ctx) => ctx,
+            Err(ex) => {
+                debug!("{:<12} - ws session ended - {ex:?}", "HANDLER");
+                let close = CloseFrame { code: close_code::POLICY, reason: "session ended".into() };
+                let _ = tx.send(Message::Close(Some(close))).await;
+                break;
+            }
+        };
+
+        // Backpressure, the client's sends block once the socket buffers are full.
+        let Ok(permit) = in_flight.clone().acquire_owned().await else {
+            break;
+        };
+        let (db_context, tx) = (db_context.clone(), tx.clone());
+        tokio::spawn(async move {
+            let response = handle_rpc_message(ctx, db_context, &text, lang, client_ip).await;
+            drop(permit);
+            let _ = tx.send(Message::Text(response.to_string())).await;
+        });
+    }
+
+    drop(tx);
+    let _ = writer.await;
+
+    debug!("{:<12} - ws connection closed", "HANDLER");
+}
+
+async fn handle_rpc_message(
+    ctx: Ctx,
+    db_context: DbContext,
+    text: &str,
+    lang: Lang,
+    client_ip: Option<IpAddr>,
+) -> Value {
+    let time_in = Instant::now();
+    // Each call of the connection gets its own id.
+    let req_id = Uuid::new_v4().to_string();
+
+    let (rpc_info, result) = match from_str::<RpcRequest>(text) {
+        Ok(rpc_req) => {
+            let rpc_info = RpcInfo {
+                id: rpc_req.id.clone(),
+                method: rpc_req.method.clone(),
+                params: rpc_req.params.clone(),
+            };
+            let call_ctx = ctx.clone().with_req_id(req_id.as_str());
+            let result = match check_rpc_limits(&ctx, &rpc_req.method).await {
+                Ok(()) => rpc_dispatch(call_ctx, db_context, rpc_req.method, rpc_req.params).await,
+                Err(ex) => Err(ex),
+            };
+
+            (Some(rpc_info), result)
+        }
+        Err(_) => (None, Err(Error::RpcFailJsonRequest)),
+    };
+
+    let (body, service_error, client_error) = match result {
+        Ok(result) => {
+            let body = json!({
+                "id": rpc_info.as_ref().and_then(|rpc| rpc.id.clone()),
+                "result": result
+            });
+
+            (body, None, None)
+        }
+        Err(service_error) => {
+            let (_, client_error) = service_error.client_status_and_error();
+            let body = rpc_error_body(rpc_info.as_ref(), &client_error, &req_id, lang);
+
+            (body, Some(service_error), Some(client_error))
+        }
+    };
+
+    // Only the calls parsed, the others have no method to label.
+    if let Some(rpc_info) = rpc_info.as_ref() {
+        metrics().record_rpc_call(
+            &rpc_info.method,
+            client_error.as_ref().map(|client_error| client_error.as_ref()),
+            time_in.elapsed(),
+        );
+    }
+
+    log_request(RequestLog {
+        req_id: &req_id,
+        method: &Method::GET,
+        uri: &Uri::from_static(WS_PATH),
+        status: None,
+        duration: time_in.elapsed(),
+        client_ip,
+        rpc_info: rpc_info.as_ref(),
+        ctx: Some(&ctx),
+        service_error: service_error.as_ref(),
+        client_error: client_error.as_ref(),
+    });
+
+    body
+}
+
+/// The same buckets as the calls over HTTP.
+async fn check_rpc_limits(ctx: &Ctx, rpc_method: &str) -> Result<()> {
+    check_rpc_user(ctx).await?;
+    check_rpc_method(ctx, rpc_method).await
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use anyhow::Result;
+    use serial_test::serial;
+
+    #[serial]
+    #[tokio::test]
+    async fn test_handle_rpc_message_ok() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let fx_message = r#"{"id": 7, "method": "list_task"}"#;
+
+        // -- Exec
+        let res = handle_rpc_message(Ctx::root_ctx(), db_context, fx_message, Lang::En, None).await;
+
+        // -- Check
+        assert_eq!(res["id"], 7);
+        assert!(res["result"].is_array(), "Should have an array result but was `{res}`");
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_handle_rpc_message_err() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+
+        // -- Exec
+        let res_unknown = handle_rpc_message(
+            Ctx::root_ctx(),
+            db_context.clone(),
+            r#"{"id": "a", "method": "no_such_method"}"#,
+            Lang::En,
+            None,
+        ).await;
+        let res_invalid = handle_rpc_message(Ctx::root_ctx(), db_context, "not json", Lang::En, None).await;
+
+        // -- Check
+        assert_eq!(res_unknown["id"], "a");
+        assert_eq!(res_unknown["error"]["message"], "SERVICE_ERROR");
+        assert_eq!(res_invalid["id"], Value::Null);
+        assert_eq!(res_invalid["error"]["message"], "INVALID_PARAMS");
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,151 @@
+use serde::Serialize;
+use validator::{Validate, ValidationError, ValidationErrors, ValidationErrorsKind};
+use crate::web::{Error, Result};
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct FieldViolation {
+    pub path: String,
+    pub reason: String,
+}
+
+impl FieldViolation {
+    pub fn new(path: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+pub fn validate<T: Validate>(value: &T) -> Result<()> {
+    value.validate().map_err(|errors| Error::ValidationFail {
+        violations: violations_from(&errors),
+    })
+}
+
+fn violations_from(errors: &ValidationErrors) -> Vec<FieldViolation> {
+    let mut violations = Vec::new();
+    collect_violations(None, errors, &mut violations);
+    violations.sort_by(|a, b| a.path.cmp(&b.path));
+
+    violations
+}
+
+fn collect_violations(
+    prefix: Option<&str>,
+    errors: &ValidationErrors,
+    violations: &mut Vec<FieldViolation>,
+) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            Some(prefix) => format!("{prefix}.{field}"),
+            None => field.to_string(),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                violations.extend(errors.iter().map(|e| FieldViolation::new(&path, reason(e))));
+            }
+            ValidationErrorsKind::Struct(errors) => {
+                collect_violations(Some(&path), errors, violations);
+            }
+            ValidationErrorsKind::List(items) => {
+                for (idx, errors) in items {
+                    collect_violations(Some(&format!("{path}[{idx}]")), errors, violations);
+                }
+            }
+        }
+    }
+}
+
+/// `code(param=value, ...)`, never echoing back the rejected value itself.
+fn reason(error: &ValidationError) -> String {
+    if let Some(message) = &error.message {
+        return message.to_string();
+    }
+
+    let mut params: Vec<String> = error
+        .params
+        .iter()
+        .filter(|(name, _)| *name != "value")
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    params.sort();
+
+    if params.is_empty() {
+        error.code.to_string()
+    } else {
+        format!("{}({})", error.code, params.join(", "))
+    }
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use crate::model::task::TaskForCreate;
+    use crate::web::rpc::{ParamsForCreate, ParamsId};
+
+    #[test]
+    fn test_validate_nested_path_ok() -> Result<()> {
+        // -- Fixtures
+        let fx_params = ParamsForCreate {
+            data: TaskForCreate {
+                title: "a".repeat(300),
+            },
+        };
+
+        // -- Exec
+        let res = validate(&fx_params);
+
+        // -- Check
+        let Err(Error::ValidationFail { violations }) = res else {
+            panic!("Should have failed validation but was `{res:?}`");
+        };
+        assert_eq!(
+            violations,
+            vec![FieldViolation::new("data.title", "length(max=256, min=1)")]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_blank_title_err() -> Result<()> {
+        // -- Fixtures
+        let fx_params = ParamsForCreate {
+            data: TaskForCreate {
+                title: "   ".to_string(),
+            },
+        };
+
+        // -- Exec
+        let res = validate(&fx_params);
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(Error::ValidationFail { violations })
+                if violations == &vec![FieldViolation::new("data.title", "not_blank")]),
+            "Should have matched a `not_blank` violation but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_range_err() -> Result<()> {
+        // -- Exec
+        let res = validate(&ParamsId { id: -1 });
+
+        // -- Check
+        assert!(
+            matches!(&res, Err(Error::ValidationFail { violations })
+                if violations == &vec![FieldViolation::new("id", "range(min=1)")]),
+            "Should have matched a `range` violation but was `{res:?}`"
+        );
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
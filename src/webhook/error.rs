@@ -0,0 +1,22 @@
+use std::fmt::Formatter;
+use derive_more::From;
+use serde::Serialize;
+use crate::{model, pwd};
+
+pub type Result<T> = core::result::Result<T, Error>;
+
+#[derive(Debug, Serialize, From)]
+pub enum Error {
+    #[from]
+    Model(model::Error),
+    #[from]
+    Pwd(pwd::Error),
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,583 @@
+mod error;
+mod resolve;
+
+pub use self::error::{Error, Result};
+pub use self::resolve::{Lookup, PublicOnlyResolver, SystemLookup};
+
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::http::header::CONTENT_TYPE;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::Notify;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+use crate::ctx::Ctx;
+use crate::event::EventEnvelope;
+use crate::model::webhook::{
+    WebhookDeliveryForSend, WebhookDeliveryRepository, WebhookRepository,
+};
+use crate::model::DbContext;
+use crate::pwd::{hmac_sha512_hash, ContentToHash};
+use crate::utils::task_utils::{spawn_worker, WorkerHandle};
+use crate::utils::time_utils::backoff_delay;
+
+/// `hmac_sha512(secret, body + event_id)`, b64u encoded.
+pub const SIGNATURE_HEADER: &str = "x-webhook-signature";
+pub const EVENT_ID_HEADER: &str = "x-webhook-id";
+pub const EVENT_TYPE_HEADER: &str = "x-webhook-event";
+
+#[derive(Debug, Clone)]
+pub struct DeliverySettings {
+    /// Attempts per delivery before it is marked `failed`.
+    pub max_attempts: i32,
+    /// Delay before the first retry, doubled on each following one.
+    pub retry_base: Duration,
+    pub retry_max: Duration,
+    /// Consecutive failed attempts (across deliveries) before the webhook is disabled.
+    pub disable_after: i32,
+    pub request_timeout: Duration,
+    pub batch_size: i64,
+    pub poll_interval: Duration,
+    /// Lets the webhooks target loopback, private and link-local addresses, e.g. in dev.
+    pub allow_private_targets: bool,
+}
+
+impl Default for DeliverySettings {
+    fn default() -> Self {
+        Self {
+            max_attempts: 6,
+            retry_base: Duration::from_secs(10),
+            retry_max: Duration::from_secs(3600),
+            disable_after: 20,
+            request_timeout: Duration::from_secs(10),
+            batch_size: 16,
+            poll_interval: Duration::from_secs(5),
+            allow_private_targets: false,
+        }
+    }
+}
+
+/// Spawns the dispatcher (bus events to queued deliveries)
+/// and the delivery worker.
+pub fn start(db_context: DbContext, settings: DeliverySettings) -> Vec<WorkerHandle> {
+    let wake_up = Arc::new(Notify::new());
+
+    vec![
+        spawn_worker("webhook_dispatcher", run_dispatcher(db_context.clone(), wake_up.clone())),
+        spawn_worker("webhook_delivery", run_worker(db_context, settings, wake_up)),
+    ]
+}
+
+async fn run_dispatcher(db_context: DbContext, wake_up: Arc<Notify>) {
+    let mut rx = db_context.events().subscribe();
+
+    loop {
+        match rx.recv().await {
+            Ok(envelope) => match enqueue_for_event(&db_context, &envelope).await {
+                Ok(0) => {}
+                Ok(_) => wake_up.notify_one(),
+                Err(ex) => warn!("{:<12} - fail to enqueue {}: {ex}", "WEBHOOK", envelope.id),
+            },
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("{:<12} - dispatcher lagged, {skipped} event(s) skipped", "WEBHOOK");
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+async fn run_worker(db_context: DbContext, settings: DeliverySettings, wake_up: Arc<Notify>) {
+    let http = http_client(&settings, Arc::new(SystemLookup));
+
+    info!("{:<12} - delivery worker started", "WEBHOOK");
+
+    loop {
+        match deliver_due(&db_context, &http, &settings).await {
+            Ok(count) if count as i64 == settings.batch_size => continue,
+            Ok(_) => {}
+            Err(ex) => warn!("{:<12} - delivery batch failed: {ex}", "WEBHOOK"),
+        }
+
+        let _ = tokio::time::timeout(settings.poll_interval, wake_up.notified()).await;
+    }
+}
+
+/// Unless the private targets are allowed, the hosts are resolved by a
+/// `PublicOnlyResolver`. A redirect would reach a target which was not checked.
+pub fn http_client(settings: &DeliverySettings, lookup: Arc<dyn Lookup>) -> reqwest::Client {
+    let mut builder = reqwest::Client::builder()
+        .timeout(settings.request_timeout)
+        .redirect(reqwest::redirect::Policy::none());
+    if !settings.allow_private_targets {
+        builder = builder.dns_resolver(Arc::new(PublicOnlyResolver::new(lookup)));
+    }
+
+    builder.build().unwrap_or_default()
+}
+
+/// Queues one delivery per enabled webhook subscribed to the event
+/// whose owner may see it.
+pub async fn enqueue_for_event(db_context: &DbContext, envelope: &EventEnvelope) -> Result<usize> {
+    let root_ctx = Ctx::root_ctx();
+    let event_type = envelope.event.name();
+
+    let webhooks = WebhookRepository::list_for_event_type(&root_ctx, db_context, event_type).await?;
+
+    let mut count = 0;
+    for webhook in webhooks {
+        let visible = Ctx::new(webhook.cid).is_ok_and(|ctx| envelope.event.is_visible_to(&ctx));
+        if !visible {
+            continue;
+        }
+
+        WebhookDeliveryRepository::enqueue(&root_ctx, db_context, webhook.id, envelope).await?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/// Sends one batch of due deliveries, returns how many were attempted.
+pub async fn deliver_due(
+    db_context: &DbContext,
+    http: &reqwest::Client,
+    settings: &DeliverySettings,
+) -> Result<usize> {
+    let root_ctx = Ctx::root_ctx();
+
+    // The batch is sent one delivery after the other, leased for as long as
+    // all its requests can take, plus one for the database updates.
+    let lease_sec = settings.request_timeout.as_secs_f64() * (settings.batch_size + 1) as f64;
+    let deliveries =
+        WebhookDeliveryRepository::claim_due(&root_ctx, db_context, settings.batch_size, lease_sec)
+            .await?;
+
+    let count = deliveries.len();
+    // A failing delivery does not hold back the rest of the batch, it is
+    // claimed again once its lease expires. A lost lease is only reported,
+    // the delivery is then owned by the worker which claimed it again.
+    for delivery in deliveries {
+        let id = delivery.id;
+        if let Err(ex) = deliver(&root_ctx, db_context, http, settings, delivery).await {
+            warn!("{:<12} - fail to deliver {id}: {ex}", "WEBHOOK");
+        }
+    }
+
+    Ok(count)
+}
+
+async fn deliver(
+    ctx: &Ctx,
+    db_context: &DbContext,
+    http: &reqwest::Client,
+    settings: &DeliverySettings,
+    delivery: WebhookDeliveryForSend,
+) -> Result<()> {
+    debug!("{:<12} - deliver {} to {}", "WEBHOOK", delivery.id, delivery.url);
+
+    let body = delivery.payload.to_string();
+    let signature = sign(&delivery.secret, delivery.event_id, &body)?;
+
+    let res = match check_target(&delivery.url, settings.allow_private_targets) {
+        Ok(()) => http
+            .post(&delivery.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, signature)
+            .header(EVENT_ID_HEADER, delivery.event_id.to_string())
+            .header(EVENT_TYPE_HEADER, &delivery.event_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|ex| error_with_sources(&ex)),
+        Err(reason) => Err(reason),
+    };
+
+    let (status_code, error) = match res {
+        Ok(res) if res.status().is_success() => {
+            WebhookDeliveryRepository::mark_delivered(ctx, db_context, &delivery, res.status().as_u16())
+                .await?;
+            WebhookRepository::record_success(ctx, db_context, delivery.webhook_id).await?;
+
+            return Ok(());
+        }
+        Ok(res) => (Some(res.status().as_u16()), format!("HTTP {}", res.status())),
+        Err(error) => (None, error),
+    };
+
+    let attempts = delivery.attempts + 1;
+    let retry_in_sec = (attempts < settings.max_attempts)
+        .then(|| backoff_delay(settings.retry_base, settings.retry_max, attempts).as_secs_f64());
+
+    WebhookDeliveryRepository::mark_attempt_failed(
+        ctx,
+        db_context,
+        &delivery,
+        status_code,
+        error,
+        retry_in_sec,
+    ).await?;
+
+    let disabled =
+        WebhookRepository::record_failure(ctx, db_context, delivery.webhook_id, settings.disable_after)
+            .await?;
+    if disabled {
+        warn!("{:<12} - webhook {} disabled after repeated failures", "WEBHOOK", delivery.webhook_id);
+    }
+
+    Ok(())
+}
+
+/// A webhook cannot reach the service's own network (e.g. the cloud metadata
+/// address). An IP host is checked here, as it is never resolved, a name is
+/// checked once resolved, by the `PublicOnlyResolver` of the client. The
+/// error is the reason, recorded as the attempt error.
+fn check_target(url: &str, allow_private: bool) -> core::result::Result<(), String> {
+    let url = reqwest::Url::parse(url).map_err(|ex| format!("invalid url: {ex}"))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("unsupported scheme: {}", url.scheme()));
+    }
+    if allow_private {
+        return Ok(());
+    }
+
+    let host = url.host_str().ok_or("url without host")?;
+    // Without the brackets of an IPv6 literal.
+    let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() else {
+        return Ok(());
+    };
+
+    if !is_public_ip(ip) {
+        return Err(format!("target address not allowed: {ip}"));
+    }
+
+    Ok(())
+}
+
+/// The reqwest errors only display their own level, e.g. not the refused address.
+fn error_with_sources(ex: &dyn std::error::Error) -> String {
+    let mut message = ex.to_string();
+    let mut source = ex.source();
+    while let Some(ex) = source {
+        message.push_str(&format!(": {ex}"));
+        source = ex.source();
+    }
+
+    message
+}
+
+fn is_public_ip(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                // Shared address space (carrier-grade NAT), 100.64.0.0/10
+                || (a == 100 && (b & 0b1100_0000) == 64)
+                // "This network", 0.0.0.0/8
+                || a == 0)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_ip(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    // Unique local, fc00::/7
+                    || (first & 0xfe00) == 0xfc00
+                    // Link-local, fe80::/10
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+/// Same HMAC-SHA-512 as the password hashing, keyed with the webhook
+/// secret and salted with the event id, so a receiver can check both.
+pub fn sign(secret: &str, event_id: Uuid, body: &str) -> Result<String> {
+    let signature = hmac_sha512_hash(
+        secret.as_bytes(),
+        &ContentToHash { content: body.to_string(), salt: event_id },
+    )?;
+
+    Ok(signature)
+}
+
+// region: -- Tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::_dev_utils;
+    use crate::event::Event;
+    use crate::model::task::Task;
+    use crate::model::webhook::{WebhookForCreate, WebhookForUpdate};
+    use anyhow::Result;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use axum::Router;
+    use serial_test::serial;
+    use std::sync::Mutex;
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+    /// Stand-in receiver answering every call with `status`.
+    async fn spawn_receiver(status: StatusCode) -> Result<(String, Received)> {
+        let received: Received = Arc::default();
+
+        let app = Router::new().route(
+            "/hook",
+            post({
+                let received = received.clone();
+                move |headers: HeaderMap, body: String| async move {
+                    received.lock().unwrap().push((headers, body));
+                    status
+                }
+            }),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/hook", listener.local_addr()?);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        Ok((url, received))
+    }
+
+    fn fx_envelope(title: &str) -> EventEnvelope {
+        EventEnvelope {
+            id: Uuid::new_v4(),
+            event: Event::TaskCreated {
+                actor_id: 1000,
+                task: Task { id: 1000, title: title.to_string() },
+            },
+        }
+    }
+
+    fn fx_settings() -> DeliverySettings {
+        DeliverySettings {
+            max_attempts: 2,
+            retry_base: Duration::ZERO,
+            disable_after: 2,
+            // The stand-in receivers are on the loopback.
+            allow_private_targets: true,
+            ..Default::default()
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_deliver_ok_signed() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let fx_secret = "test_deliver_ok_signed secret";
+        let (url, received) = spawn_receiver(StatusCode::OK).await?;
+        let webhook_id = WebhookRepository::create(
+            &ctx,
+            &db_context,
+            WebhookForCreate {
+                url,
+                secret: fx_secret.to_string(),
+                event_types: vec!["task.created".to_string()],
+            },
+        ).await?;
+        let envelope = fx_envelope("test_deliver_ok_signed");
+
+        // -- Exec
+        // Twice, the second enqueue is a no-op.
+        enqueue_for_event(&db_context, &envelope).await?;
+        enqueue_for_event(&db_context, &envelope).await?;
+        let count = deliver_due(&db_context, &reqwest::Client::new(), &fx_settings()).await?;
+
+        // -- Check
+        assert_eq!(count, 1);
+        let received = received.lock().unwrap().clone();
+        let (headers, body) = &received[0];
+        assert_eq!(headers[EVENT_ID_HEADER], envelope.id.to_string());
+        assert_eq!(headers[SIGNATURE_HEADER], sign(fx_secret, envelope.id, body)?);
+
+        let deliveries = WebhookDeliveryRepository::list_for_webhook(&ctx, &db_context, webhook_id).await?;
+        assert_eq!(deliveries.len(), 1);
+        assert_eq!(deliveries[0].status, "delivered");
+        assert_eq!(deliveries[0].last_status_code, Some(200));
+
+        // -- Clean
+        WebhookRepository::delete(&ctx, &db_context, webhook_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_deliver_err_retry_then_disable() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let (url, received) = spawn_receiver(StatusCode::INTERNAL_SERVER_ERROR).await?;
+        let webhook_id = WebhookRepository::create(
+            &ctx,
+            &db_context,
+            WebhookForCreate {
+                url,
+                secret: "test_deliver_err_retry_then_disable secret".to_string(),
+                event_types: vec!["task.created".to_string()],
+            },
+        ).await?;
+        let http = reqwest::Client::new();
+        let settings = fx_settings();
+
+        // -- Exec
+        enqueue_for_event(&db_context, &fx_envelope("test_deliver_err_retry_then_disable")).await?;
+        deliver_due(&db_context, &http, &settings).await?;
+        deliver_due(&db_context, &http, &settings).await?;
+        let count_after_failed = deliver_due(&db_context, &http, &settings).await?;
+
+        // -- Check
+        assert_eq!(received.lock().unwrap().len(), 2);
+        assert_eq!(count_after_failed, 0);
+
+        let deliveries = WebhookDeliveryRepository::list_for_webhook(&ctx, &db_context, webhook_id).await?;
+        assert_eq!(deliveries[0].status, "failed");
+        assert_eq!(deliveries[0].attempts, 2);
+        assert_eq!(deliveries[0].last_status_code, Some(500));
+
+        let webhook = WebhookRepository::get(&ctx, &db_context, webhook_id).await?;
+        assert!(!webhook.enabled, "Should have been disabled");
+
+        // Re-enabling resets the failure count.
+        WebhookRepository::update(
+            &ctx,
+            &db_context,
+            webhook_id,
+            WebhookForUpdate { url: None, secret: None, event_types: None, enabled: Some(true) },
+        ).await?;
+        let webhook = WebhookRepository::get(&ctx, &db_context, webhook_id).await?;
+        assert!(webhook.enabled);
+        assert_eq!(webhook.failure_count, 0);
+
+        // -- Clean
+        WebhookRepository::delete(&ctx, &db_context, webhook_id).await?;
+
+        Ok(())
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_deliver_err_private_target() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let (url, received) = spawn_receiver(StatusCode::OK).await?;
+        let webhook_id = WebhookRepository::create(
+            &ctx,
+            &db_context,
+            WebhookForCreate {
+                url,
+                secret: "test_deliver_err_private_target secret".to_string(),
+                event_types: vec!["task.created".to_string()],
+            },
+        ).await?;
+        let settings = DeliverySettings { allow_private_targets: false, ..fx_settings() };
+
+        // -- Exec
+        enqueue_for_event(&db_context, &fx_envelope("test_deliver_err_private_target")).await?;
+        deliver_due(&db_context, &reqwest::Client::new(), &settings).await?;
+
+        // -- Check
+        assert!(received.lock().unwrap().is_empty(), "Should not have sent to the loopback");
+        let deliveries = WebhookDeliveryRepository::list_for_webhook(&ctx, &db_context, webhook_id).await?;
+        let last_error = deliveries[0].last_error.as_deref().unwrap_or_default();
+        assert!(last_error.starts_with("target address not allowed"), "got {last_error:?}");
+
+        // -- Clean
+        WebhookRepository::delete(&ctx, &db_context, webhook_id).await?;
+
+        Ok(())
+    }
+
+    /// Answers a public address first, then the loopback.
+    #[derive(Default)]
+    struct RebindingLookup {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl Lookup for RebindingLookup {
+        async fn lookup(&self, _host: &str) -> std::io::Result<Vec<IpAddr>> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            let ip = if call == 0 { "93.184.216.34" } else { "127.0.0.1" };
+
+            Ok(vec![ip.parse().expect("valid ip")])
+        }
+    }
+
+    #[serial]
+    #[tokio::test]
+    async fn test_deliver_err_dns_rebinding() -> Result<()> {
+        // -- Setup & Fixtures
+        let db_context = _dev_utils::init_test().await;
+        let ctx = Ctx::new(1000)?;
+        let (url, received) = spawn_receiver(StatusCode::OK).await?;
+        let url = url.replace("127.0.0.1", "rebind.test");
+        let webhook_id = WebhookRepository::create(
+            &ctx,
+            &db_context,
+            WebhookForCreate {
+                url,
+                secret: "test_deliver_err_dns_rebinding secret".to_string(),
+                event_types: vec!["task.created".to_string()],
+            },
+        ).await?;
+        let settings = DeliverySettings { allow_private_targets: false, ..fx_settings() };
+        let lookup = Arc::new(RebindingLookup::default());
+        // The first resolution is public, e.g. as seen by a check made before the request.
+        assert!(is_public_ip(lookup.lookup("rebind.test").await?[0]));
+        let http = http_client(&settings, lookup.clone());
+
+        // -- Exec
+        enqueue_for_event(&db_context, &fx_envelope("test_deliver_err_dns_rebinding")).await?;
+        deliver_due(&db_context, &http, &settings).await?;
+
+        // -- Check
+        assert!(received.lock().unwrap().is_empty(), "Should not have sent to the loopback");
+        let deliveries = WebhookDeliveryRepository::list_for_webhook(&ctx, &db_context, webhook_id).await?;
+        let last_error = deliveries[0].last_error.as_deref().unwrap_or_default();
+        assert!(last_error.contains("target address not allowed for rebind.test"), "got {last_error:?}");
+
+        // -- Clean
+        WebhookRepository::delete(&ctx, &db_context, webhook_id).await?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_public_ip() -> Result<()> {
+        // -- Setup & Fixtures
+        let fx_cases = [
+            ("93.184.216.34", true),
+            ("2606:2800:220:1:248:1893:25c8:1946", true),
+            ("127.0.0.1", false),
+            ("10.1.2.3", false),
+            ("172.16.0.1", false),
+            ("192.168.1.1", false),
+            ("169.254.169.254", false),
+            ("100.64.0.1", false),
+            ("0.0.0.0", false),
+            ("::1", false),
+            ("fd00:ec2::254", false),
+            ("fe80::1", false),
+            ("::ffff:127.0.0.1", false),
+        ];
+
+        for (fx_ip, public) in fx_cases {
+            // -- Exec & Check
+            assert_eq!(is_public_ip(fx_ip.parse()?), public, "for {fx_ip}");
+        }
+
+        Ok(())
+    }
+}
+// endregion: -- Tests
@@ -0,0 +1,60 @@
+use std::io;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use async_trait::async_trait;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use crate::webhook::is_public_ip;
+
+/// Where the webhook hosts are resolved, swapped in the tests.
+#[async_trait]
+pub trait Lookup: Send + Sync {
+    async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>>;
+}
+
+pub struct SystemLookup;
+
+#[async_trait]
+impl Lookup for SystemLookup {
+    async fn lookup(&self, host: &str) -> io::Result<Vec<IpAddr>> {
+        let addrs = tokio::net::lookup_host((host, 0)).await?;
+
+        Ok(addrs.map(|addr| addr.ip()).collect())
+    }
+}
+
+/// The resolver of the webhook client, the addresses which are not public
+/// are dropped. The check is made on the very addresses connected to, a
+/// host answering another address on a second lookup (DNS rebinding) is
+/// checked again.
+pub struct PublicOnlyResolver {
+    lookup: Arc<dyn Lookup>,
+}
+
+impl PublicOnlyResolver {
+    pub fn new(lookup: Arc<dyn Lookup>) -> Self {
+        Self { lookup }
+    }
+}
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let lookup = self.lookup.clone();
+        let host = name.as_str().to_string();
+
+        Box::pin(async move {
+            let ips = lookup.lookup(&host).await?;
+            // The port is set by the connector.
+            let addrs: Vec<SocketAddr> = ips
+                .into_iter()
+                .filter(|ip| is_public_ip(*ip))
+                .map(|ip| SocketAddr::new(ip, 0))
+                .collect();
+
+            if addrs.is_empty() {
+                return Err(format!("target address not allowed for {host}").into());
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}